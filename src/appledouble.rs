@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// The first four bytes of every AppleDouble file, stored big-endian
+pub const MAGIC: u32 = 0x0005_1607;
+
+/// Which AppleDouble `._` files a run picks up besides `.DS_Store`
+///
+/// Non-HFS volumes keep a file's resource fork and extended attributes in a `._` file
+/// next to it. Once the data file is gone the `._` file is an orphan and safe to remove;
+/// while the data file exists, removing it loses Finder tags, labels and the like.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppleDoubleMode {
+    /// Leave every `._` file alone
+    #[default]
+    Off,
+    /// Only `._` files whose data file no longer exists
+    Orphans,
+    /// Every `._` file, whether or not its data file exists
+    All,
+}
+
+impl AppleDoubleMode {
+    #[must_use]
+    pub fn from_flags(enabled: bool, include_paired: bool) -> Self {
+        match (enabled, include_paired) {
+            (false, _) => Self::Off,
+            (true, false) => Self::Orphans,
+            (true, true) => Self::All,
+        }
+    }
+
+    #[must_use]
+    pub fn is_enabled(self) -> bool {
+        self != Self::Off
+    }
+}
+
+/// The data file a `._` file belongs to, if the name looks like an AppleDouble file
+#[must_use]
+pub fn data_file(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let data_name = name.strip_prefix("._").filter(|rest| !rest.is_empty())?;
+    Some(path.with_file_name(data_name))
+}
+
+/// Whether `path` starts with the AppleDouble header magic
+///
+/// Anything unreadable or shorter than the magic is not treated as AppleDouble, so a
+/// user's own file that happens to start with `._` is never picked up.
+pub async fn has_magic(path: &Path) -> bool {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return false;
+    };
+    let mut header = [0_u8; 4];
+    file.read_exact(&mut header).await.is_ok() && u32::from_be_bytes(header) == MAGIC
+}
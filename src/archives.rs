@@ -0,0 +1,306 @@
+use color_eyre::eyre::{eyre, Result};
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// What a run does with archives that carry macOS cruft
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArchiveMode {
+    /// Archives are not looked at
+    #[default]
+    Off,
+    /// Archives are opened during the walk and the ones with cruft are listed
+    Report,
+    /// As `Report`, and the archives are rewritten without the cruft entries
+    Clean,
+}
+
+impl ArchiveMode {
+    #[must_use]
+    pub fn is_enabled(self) -> bool {
+        self != Self::Off
+    }
+}
+
+/// The archive formats `dds` can look inside
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveKind {
+    /// Recognise an archive by its file name
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+/// An archive holding entries that only macOS put there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveHit {
+    pub path: PathBuf,
+    pub kind: ArchiveKind,
+    /// Names of the entries a clean would remove, in archive order
+    pub cruft: Vec<String>,
+    /// How many entries the archive holds in total
+    pub entries: usize,
+}
+
+/// What rewriting an archive achieved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanedArchive {
+    pub removed: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Where the untouched original was kept, if a backup was asked for
+    pub backup: Option<PathBuf>,
+}
+
+/// Whether an archive entry is something macOS added: anything under `__MACOSX/`,
+/// any `.DS_Store` and any AppleDouble `._` file
+#[must_use]
+pub fn is_cruft(entry_name: &str) -> bool {
+    let parts: Vec<&str> = entry_name
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty())
+        .collect();
+    parts.contains(&"__MACOSX")
+        || parts
+            .last()
+            .is_some_and(|name| *name == ".DS_Store" || name.starts_with("._"))
+}
+
+/// Look inside an archive, returning it only if it holds cruft
+pub fn scan(path: &Path) -> Result<Option<ArchiveHit>> {
+    let Some(kind) = ArchiveKind::from_path(path) else {
+        return Ok(None);
+    };
+    let names = match kind {
+        ArchiveKind::Zip => zip_entry_names(path)?,
+        ArchiveKind::Tar | ArchiveKind::TarGz | ArchiveKind::TarZst => {
+            tar_entry_names(tar_reader(path, kind)?)?
+        }
+    };
+    let entries = names.len();
+    let cruft: Vec<String> = names.into_iter().filter(|name| is_cruft(name)).collect();
+    if cruft.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ArchiveHit {
+        path: path.to_path_buf(),
+        kind,
+        cruft,
+        entries,
+    }))
+}
+
+/// Rewrite an archive without its cruft entries
+///
+/// The new archive is written to a temporary file next to the original and renamed over
+/// it, so the archive is never left half-written. With `backup`, the original is kept
+/// as `<name>.bak` first; an existing backup is never overwritten. A symlink is never
+/// rewritten, since the rename would replace the link rather than the archive behind it.
+pub fn clean(hit: &ArchiveHit, backup: bool) -> Result<CleanedArchive> {
+    let path = &hit.path;
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        return Err(eyre!("{} is a symlink; not rewriting it", path.display()));
+    }
+    let parent = path
+        .parent()
+        .ok_or_else(|| eyre!("{} has no parent directory", path.display()))?;
+
+    let backup_path = if backup {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".bak");
+        let backup_path = path.with_file_name(name);
+        if backup_path.exists() {
+            return Err(eyre!(
+                "Backup {} already exists; not rewriting {}",
+                backup_path.display(),
+                path.display()
+            ));
+        }
+        Some(backup_path)
+    } else {
+        None
+    };
+
+    let mut temp = NamedTempFile::new_in(parent)?;
+    let removed = match hit.kind {
+        ArchiveKind::Zip => rewrite_zip(path, temp.as_file_mut())?,
+        ArchiveKind::Tar | ArchiveKind::TarGz | ArchiveKind::TarZst => {
+            rewrite_tar(path, hit.kind, temp.as_file_mut())?
+        }
+    };
+    temp.as_file().sync_all()?;
+    fs::set_permissions(temp.path(), metadata.permissions())?;
+
+    if let Some(backup_path) = &backup_path {
+        // A hard link costs nothing; fall back to a copy across devices and the like
+        if fs::hard_link(path, backup_path).is_err() {
+            fs::copy(path, backup_path)?;
+        }
+    }
+    let file = temp.persist(path).map_err(|err| err.error)?;
+
+    Ok(CleanedArchive {
+        removed,
+        bytes_before: metadata.len(),
+        bytes_after: file.metadata()?.len(),
+        backup: backup_path,
+    })
+}
+
+fn zip_entry_names(path: &Path) -> Result<Vec<String>> {
+    let archive = zip::ZipArchive::new(BufReader::new(fs::File::open(path)?))?;
+    Ok(archive.file_names().map(str::to_string).collect())
+}
+
+/// Copy every entry but the cruft, still compressed, keeping names, order and comment
+fn rewrite_zip(path: &Path, out: &mut fs::File) -> Result<usize> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(fs::File::open(path)?))?;
+    let mut writer = zip::ZipWriter::new(BufWriter::new(out));
+    let mut removed = 0;
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index)?;
+        if is_cruft(entry.name()) {
+            removed += 1;
+            continue;
+        }
+        writer.raw_copy_file(entry)?;
+    }
+    writer.set_raw_comment(archive.comment().into());
+    writer
+        .finish()?
+        .into_inner()
+        .map_err(|err| err.into_error())?;
+    Ok(removed)
+}
+
+/// The uncompressed tar stream of a tar, tar.gz or tar.zst archive
+fn tar_reader(path: &Path, kind: ArchiveKind) -> Result<Box<dyn Read>> {
+    let file = BufReader::new(fs::File::open(path)?);
+    Ok(match kind {
+        ArchiveKind::Tar => Box::new(file),
+        ArchiveKind::TarGz => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        ArchiveKind::TarZst => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+        ArchiveKind::Zip => return Err(eyre!("{} is not a tar archive", path.display())),
+    })
+}
+
+fn tar_entry_names<R: Read>(input: R) -> Result<Vec<String>> {
+    let mut archive = tar::Archive::new(input);
+    let mut names = Vec::new();
+    for entry in archive.entries()? {
+        names.push(entry?.path()?.to_string_lossy().into_owned());
+    }
+    Ok(names)
+}
+
+/// Recompress the filtered tar stream the same way the original was compressed
+fn rewrite_tar(path: &Path, kind: ArchiveKind, out: &mut fs::File) -> Result<usize> {
+    let input = tar_reader(path, kind)?;
+    let out = BufWriter::new(out);
+    let removed = match kind {
+        ArchiveKind::Tar => {
+            let (out, removed) = copy_tar_members(input, out)?;
+            out.into_inner().map_err(|err| err.into_error())?;
+            removed
+        }
+        ArchiveKind::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+            let (encoder, removed) = copy_tar_members(input, encoder)?;
+            encoder.finish()?.flush()?;
+            removed
+        }
+        ArchiveKind::TarZst => {
+            let encoder = zstd::stream::write::Encoder::new(out, 0)?;
+            let (encoder, removed) = copy_tar_members(input, encoder)?;
+            encoder.finish()?.flush()?;
+            removed
+        }
+        ArchiveKind::Zip => return Err(eyre!("{} is not a tar archive", path.display())),
+    };
+    Ok(removed)
+}
+
+/// Copy every member but the cruft, header blocks and all, in their original order
+///
+/// Members are read raw, so GNU long-name and PAX headers come through as members of
+/// their own. They are held back until the member they describe is seen, and dropped
+/// with it if it is cruft, which keeps ownership, modes, times and extended attributes
+/// of everything else exactly as they were.
+fn copy_tar_members<R: Read, W: Write>(input: R, output: W) -> Result<(W, usize)> {
+    let mut archive = tar::Archive::new(input);
+    let mut builder = tar::Builder::new(output);
+    let mut pending: Vec<(tar::Header, Vec<u8>)> = Vec::new();
+    let mut removed = 0;
+
+    for entry in archive.entries()?.raw(true) {
+        let mut entry = entry?;
+        let header = entry.header().clone();
+        let entry_type = header.entry_type();
+        if entry_type.is_gnu_longname()
+            || entry_type.is_gnu_longlink()
+            || entry_type.is_pax_local_extensions()
+        {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            pending.push((header, data));
+            continue;
+        }
+
+        if is_cruft(&tar_member_name(&header, &pending)) {
+            removed += 1;
+            pending.clear();
+            continue;
+        }
+        for (pending_header, data) in pending.drain(..) {
+            builder.append(&pending_header, data.as_slice())?;
+        }
+        builder.append(&header, &mut entry)?;
+    }
+
+    Ok((builder.into_inner()?, removed))
+}
+
+/// The full name of a raw member, taking any long-name or PAX header before it into account
+fn tar_member_name(header: &tar::Header, pending: &[(tar::Header, Vec<u8>)]) -> String {
+    for (pending_header, data) in pending.iter().rev() {
+        let entry_type = pending_header.entry_type();
+        if entry_type.is_gnu_longname() {
+            let end = data
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(data.len());
+            return String::from_utf8_lossy(&data[..end]).into_owned();
+        }
+        if entry_type.is_pax_local_extensions() {
+            let path = tar::PaxExtensions::new(data)
+                .filter_map(Result::ok)
+                .find(|extension| extension.key() == Ok("path"))
+                .map(|extension| String::from_utf8_lossy(extension.value_bytes()).into_owned());
+            if let Some(path) = path {
+                return path;
+            }
+        }
+    }
+    String::from_utf8_lossy(&header.path_bytes()).into_owned()
+}
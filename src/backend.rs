@@ -0,0 +1,667 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use color_eyre::eyre::Result;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::cache::{
+    Cache, DeletionRecord, DirectoryState, DirectoryStatus, HeartbeatGuard, Hit, SearchSession,
+    SearchSessionStatus, WorkItem,
+};
+use crate::roots;
+
+/// The cache operations the directory walker depends on
+///
+/// [`Cache`] is the persistent `SQLite` implementation; [`MemoryCache`] keeps everything
+/// in process memory for tests and for embedding the walker without a database.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    // ===== DIRECTORY STATUS =====
+
+    async fn get_directory_status(&self, path: &Path) -> Result<DirectoryStatus>;
+
+    /// Subdirectories recorded under `path` by earlier searches
+    async fn get_cached_children(&self, path: &Path) -> Result<Vec<PathBuf>>;
+
+    async fn mark_searching(&mut self, path: &Path) -> Result<()>;
+
+    async fn mark_completed_batch(&mut self, states: &[DirectoryState]) -> Result<()>;
+
+    /// `.DS_Store` files recorded as found but not yet deleted under `root_path`
+    async fn get_undeleted_ds_store_files(
+        &self,
+        root_path: &Path,
+        recursive: bool,
+    ) -> Result<Vec<PathBuf>>;
+
+    /// Drop entries that have aged well past the cache window
+    async fn cleanup_old_entries(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Make everything written so far durable
+    async fn flush_pending(&self) -> Result<()> {
+        Ok(())
+    }
+
+    // ===== SESSIONS =====
+
+    async fn start_session(
+        &mut self,
+        roots: &[PathBuf],
+        is_recursive: bool,
+        is_dry_run: bool,
+        profiles: &str,
+    ) -> Result<String>;
+
+    async fn resume_session(
+        &mut self,
+        roots: &[PathBuf],
+        is_recursive: bool,
+        is_dry_run: bool,
+        profiles: &str,
+    ) -> Result<Option<String>>;
+
+    async fn complete_session(&mut self) -> Result<()>;
+
+    async fn interrupt_session(&mut self) -> Result<()>;
+
+    /// Signal that the current session is still being worked on
+    async fn heartbeat(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Keep signalling that the current session is being worked on until the guard is
+    /// dropped; backends no other process can see need not bother
+    fn keep_alive(&self) -> Option<HeartbeatGuard> {
+        None
+    }
+
+    async fn get_session_searched_count(&self, session_id: &str) -> Result<usize>;
+
+    // ===== WORK QUEUE =====
+
+    async fn enqueue_work(&self, session_id: &str, path: &Path, priority: i32) -> Result<()>;
+
+    async fn enqueue_work_batch(
+        &self,
+        session_id: &str,
+        paths: &[PathBuf],
+        priority: i32,
+    ) -> Result<()>;
+
+    async fn peek_work_batch(&self, session_id: &str, batch_size: usize) -> Result<Vec<WorkItem>>;
+
+    async fn remove_work_items(&self, item_ids: &[i64]) -> Result<()>;
+
+    async fn get_work_count(&self, session_id: &str) -> Result<usize>;
+
+    // ===== FOUND FILES =====
+
+    async fn save_hits(&self, session_id: &str, hits: &[Hit]) -> Result<()>;
+
+    async fn load_hits(&self, session_id: &str) -> Result<Vec<Hit>>;
+
+    // ===== DELETION AUDIT LOG =====
+
+    async fn record_deletions(&self, records: &[DeletionRecord]) -> Result<()>;
+
+    // ===== INTERACTIVE REVIEW =====
+
+    async fn record_rejections(&self, session_id: &str, files: &[PathBuf]) -> Result<()>;
+
+    async fn load_rejections(&self, root: &Path) -> Result<Vec<PathBuf>>;
+}
+
+#[async_trait]
+impl CacheBackend for Cache {
+    async fn get_directory_status(&self, path: &Path) -> Result<DirectoryStatus> {
+        Cache::get_directory_status(self, path).await
+    }
+
+    async fn get_cached_children(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        Cache::get_cached_children(self, path).await
+    }
+
+    async fn mark_searching(&mut self, path: &Path) -> Result<()> {
+        Cache::mark_searching(self, path).await
+    }
+
+    async fn mark_completed_batch(&mut self, states: &[DirectoryState]) -> Result<()> {
+        Cache::mark_completed_batch(self, states).await
+    }
+
+    async fn get_undeleted_ds_store_files(
+        &self,
+        root_path: &Path,
+        recursive: bool,
+    ) -> Result<Vec<PathBuf>> {
+        Cache::get_undeleted_ds_store_files(self, root_path, recursive).await
+    }
+
+    async fn cleanup_old_entries(&self) -> Result<()> {
+        Cache::cleanup_old_entries(self).await
+    }
+
+    async fn flush_pending(&self) -> Result<()> {
+        Cache::flush_pending(self).await
+    }
+
+    async fn start_session(
+        &mut self,
+        roots: &[PathBuf],
+        is_recursive: bool,
+        is_dry_run: bool,
+        profiles: &str,
+    ) -> Result<String> {
+        Cache::start_session(self, roots, is_recursive, is_dry_run, profiles).await
+    }
+
+    async fn resume_session(
+        &mut self,
+        roots: &[PathBuf],
+        is_recursive: bool,
+        is_dry_run: bool,
+        profiles: &str,
+    ) -> Result<Option<String>> {
+        Cache::resume_session(self, roots, is_recursive, is_dry_run, profiles).await
+    }
+
+    async fn complete_session(&mut self) -> Result<()> {
+        Cache::complete_session(self).await
+    }
+
+    async fn interrupt_session(&mut self) -> Result<()> {
+        Cache::interrupt_session(self).await
+    }
+
+    async fn heartbeat(&self) -> Result<()> {
+        Cache::heartbeat(self).await
+    }
+
+    fn keep_alive(&self) -> Option<HeartbeatGuard> {
+        Cache::keep_alive(self)
+    }
+
+    async fn get_session_searched_count(&self, session_id: &str) -> Result<usize> {
+        Cache::get_session_searched_count(self, session_id).await
+    }
+
+    async fn enqueue_work(&self, session_id: &str, path: &Path, priority: i32) -> Result<()> {
+        Cache::enqueue_work(self, session_id, path, priority).await
+    }
+
+    async fn enqueue_work_batch(
+        &self,
+        session_id: &str,
+        paths: &[PathBuf],
+        priority: i32,
+    ) -> Result<()> {
+        Cache::enqueue_work_batch(self, session_id, paths, priority).await
+    }
+
+    async fn peek_work_batch(&self, session_id: &str, batch_size: usize) -> Result<Vec<WorkItem>> {
+        Cache::peek_work_batch(self, session_id, batch_size).await
+    }
+
+    async fn remove_work_items(&self, item_ids: &[i64]) -> Result<()> {
+        Cache::remove_work_items(self, item_ids).await
+    }
+
+    async fn get_work_count(&self, session_id: &str) -> Result<usize> {
+        Cache::get_work_count(self, session_id).await
+    }
+
+    async fn save_hits(&self, session_id: &str, hits: &[Hit]) -> Result<()> {
+        Cache::save_hits(self, session_id, hits).await
+    }
+
+    async fn load_hits(&self, session_id: &str) -> Result<Vec<Hit>> {
+        Cache::load_hits(self, session_id).await
+    }
+
+    async fn record_deletions(&self, records: &[DeletionRecord]) -> Result<()> {
+        Cache::record_deletions(self, records).await
+    }
+
+    async fn record_rejections(&self, session_id: &str, files: &[PathBuf]) -> Result<()> {
+        Cache::record_rejections(self, session_id, files).await
+    }
+
+    async fn load_rejections(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        Cache::load_rejections(self, root).await
+    }
+}
+
+/// Work queue contents, ordered by insertion id like the `work_queue` table
+#[derive(Debug, Default)]
+struct MemoryQueue {
+    items: BTreeMap<i64, WorkItem>,
+    queued: HashSet<(String, PathBuf)>,
+    next_id: i64,
+}
+
+/// A cache held entirely in process memory, with no database behind it
+///
+/// Nothing outlives the process and there is no cross-process coordination, so this is
+/// meant for tests and for embedding the walker in other tools. The queue, found-file and
+/// audit methods take `&self` to match [`Cache`], hence the mutexes around those.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    directories: HashMap<PathBuf, DirectoryState>,
+    sessions: Vec<SearchSession>,
+    queue: std::sync::Mutex<MemoryQueue>,
+    found_files: std::sync::Mutex<HashMap<String, Vec<Hit>>>,
+    deletions: std::sync::Mutex<Vec<DeletionRecord>>,
+    rejections: std::sync::Mutex<BTreeSet<PathBuf>>,
+    window_hours: u64,
+    force_refresh: bool,
+    current_session: Option<SearchSession>,
+}
+
+impl MemoryCache {
+    #[must_use]
+    pub fn new(window_hours: u64, force: bool) -> Self {
+        Self {
+            window_hours,
+            force_refresh: force,
+            ..Default::default()
+        }
+    }
+
+    /// Get current session info
+    #[must_use]
+    pub fn get_current_session(&self) -> Option<&SearchSession> {
+        self.current_session.as_ref()
+    }
+
+    /// Every removal recorded so far, oldest first
+    #[must_use]
+    pub fn deletions(&self) -> Vec<DeletionRecord> {
+        self.deletions
+            .lock()
+            .expect("Failed to acquire lock on deletions")
+            .clone()
+    }
+
+    fn cutoff(&self, window_multiplier: i64) -> i64 {
+        Utc::now().timestamp()
+            - i64::try_from(self.window_hours)
+                .unwrap_or(i64::MAX)
+                .saturating_mul(window_multiplier)
+    }
+
+    fn queue(&self) -> std::sync::MutexGuard<'_, MemoryQueue> {
+        self.queue
+            .lock()
+            .expect("Failed to acquire lock on work queue")
+    }
+
+    fn found(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<Hit>>> {
+        self.found_files
+            .lock()
+            .expect("Failed to acquire lock on found files")
+    }
+
+    fn cleanup_session(&mut self, session_id: &str) {
+        self.queue()
+            .items
+            .retain(|_, item| item.session_id != session_id);
+        self.queue()
+            .queued
+            .retain(|(queued_session, _)| queued_session != session_id);
+        self.found().remove(session_id);
+        self.sessions
+            .retain(|session| session.session_id != session_id);
+    }
+
+    fn set_status(&mut self, session_id: &str, status: SearchSessionStatus) {
+        if let Some(session) = self
+            .sessions
+            .iter_mut()
+            .find(|session| session.session_id == session_id)
+        {
+            if status == SearchSessionStatus::Completed {
+                session.completed_at = Some(Utc::now().timestamp());
+            }
+            session.status = status;
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn get_directory_status(&self, path: &Path) -> Result<DirectoryStatus> {
+        if self.force_refresh {
+            return Ok(DirectoryStatus::NotCached);
+        }
+
+        Ok(match self.directories.get(path) {
+            None => DirectoryStatus::NotCached,
+            Some(state) if !state.search_completed => DirectoryStatus::Incomplete,
+            Some(state) if state.last_searched_at <= self.cutoff(3600) => DirectoryStatus::Stale,
+            Some(state) => Cache::status_from_stamp(&state.stamp(), path),
+        })
+    }
+
+    async fn get_cached_children(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .directories
+            .keys()
+            .filter(|child| child.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
+    async fn mark_searching(&mut self, path: &Path) -> Result<()> {
+        let now = Utc::now().timestamp();
+        let state = self
+            .directories
+            .entry(path.to_path_buf())
+            .or_insert_with(|| DirectoryState {
+                path: path.to_path_buf(),
+                last_searched_at: now,
+                search_completed: false,
+                ds_store_found: false,
+                ds_store_deleted: false,
+                error_message: None,
+                mtime_ns: None,
+                inode: None,
+            });
+        state.last_searched_at = now;
+        state.search_completed = false;
+        Ok(())
+    }
+
+    async fn mark_completed_batch(&mut self, states: &[DirectoryState]) -> Result<()> {
+        for state in states {
+            self.directories.insert(state.path.clone(), state.clone());
+        }
+        Ok(())
+    }
+
+    async fn get_undeleted_ds_store_files(
+        &self,
+        root_path: &Path,
+        recursive: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = self
+            .directories
+            .values()
+            .filter(|state| {
+                state.ds_store_found && !state.ds_store_deleted && state.search_completed
+            })
+            // `Path::starts_with` compares whole components, so `/foo` never matches `/foobar`
+            .filter(|state| {
+                state.path == root_path || (recursive && state.path.starts_with(root_path))
+            })
+            .map(|state| state.path.join(".DS_Store"))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    async fn start_session(
+        &mut self,
+        roots: &[PathBuf],
+        is_recursive: bool,
+        is_dry_run: bool,
+        profiles: &str,
+    ) -> Result<String> {
+        let session_id = Uuid::new_v4().to_string();
+
+        // Clean up unfinished sessions that have aged out of the cache window
+        let cutoff = self.cutoff(3600);
+        let stale: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|session| {
+                session.started_at < cutoff && session.status != SearchSessionStatus::Completed
+            })
+            .map(|session| session.session_id.clone())
+            .collect();
+        for stale_id in stale {
+            self.cleanup_session(&stale_id);
+        }
+
+        let session = SearchSession {
+            session_id: session_id.clone(),
+            root_path: roots::common_ancestor(roots),
+            started_at: Utc::now().timestamp(),
+            completed_at: None,
+            is_recursive,
+            is_dry_run,
+            status: SearchSessionStatus::Active,
+            profiles: profiles.to_string(),
+            roots: roots::from_session_key(&roots::session_key(roots)),
+        };
+        self.sessions.push(session.clone());
+        self.current_session = Some(session);
+
+        self.enqueue_work_batch(&session_id, roots, 0).await?;
+
+        Ok(session_id)
+    }
+
+    async fn resume_session(
+        &mut self,
+        roots: &[PathBuf],
+        is_recursive: bool,
+        is_dry_run: bool,
+        profiles: &str,
+    ) -> Result<Option<String>> {
+        // Newest unfinished session for the same search; later sessions win ties
+        let root_path = roots::common_ancestor(roots);
+        let listed = roots::from_session_key(&roots::session_key(roots));
+        let candidate = self
+            .sessions
+            .iter()
+            .filter(|session| {
+                session.root_path == root_path
+                    && session.roots == listed
+                    && session.is_recursive == is_recursive
+                    && session.is_dry_run == is_dry_run
+                    && session.profiles == profiles
+                    && session.status != SearchSessionStatus::Completed
+            })
+            .max_by_key(|session| session.started_at)
+            .map(|session| session.session_id.clone());
+
+        let Some(session_id) = candidate else {
+            return Ok(None);
+        };
+
+        let has_work = self.get_work_count(&session_id).await? > 0;
+        let has_found = self
+            .found()
+            .get(&session_id)
+            .is_some_and(|files| !files.is_empty());
+
+        if !has_work && !has_found {
+            self.cleanup_session(&session_id);
+            return Ok(None);
+        }
+
+        self.set_status(&session_id, SearchSessionStatus::Active);
+        self.current_session = self
+            .sessions
+            .iter()
+            .find(|session| session.session_id == session_id)
+            .cloned();
+
+        Ok(Some(session_id))
+    }
+
+    async fn complete_session(&mut self) -> Result<()> {
+        if let Some(session) = self.current_session.take() {
+            self.set_status(&session.session_id, SearchSessionStatus::Completed);
+            let mut queue = self.queue();
+            queue
+                .items
+                .retain(|_, item| item.session_id != session.session_id);
+            queue
+                .queued
+                .retain(|(queued_session, _)| *queued_session != session.session_id);
+        }
+        Ok(())
+    }
+
+    async fn interrupt_session(&mut self) -> Result<()> {
+        if let Some(session_id) = self
+            .current_session
+            .as_ref()
+            .map(|session| session.session_id.clone())
+        {
+            self.set_status(&session_id, SearchSessionStatus::Interrupted);
+        }
+        Ok(())
+    }
+
+    async fn get_session_searched_count(&self, session_id: &str) -> Result<usize> {
+        let Some(session) = self
+            .sessions
+            .iter()
+            .find(|session| session.session_id == session_id)
+        else {
+            return Ok(0);
+        };
+
+        let end_time = session
+            .completed_at
+            .unwrap_or_else(|| Utc::now().timestamp());
+        Ok(self
+            .directories
+            .values()
+            .filter(|state| {
+                state.search_completed
+                    && (session.started_at..=end_time).contains(&state.last_searched_at)
+            })
+            .count())
+    }
+
+    async fn enqueue_work(&self, session_id: &str, path: &Path, priority: i32) -> Result<()> {
+        self.enqueue_work_batch(session_id, &[path.to_path_buf()], priority)
+            .await
+    }
+
+    async fn enqueue_work_batch(
+        &self,
+        session_id: &str,
+        paths: &[PathBuf],
+        priority: i32,
+    ) -> Result<()> {
+        let now = Utc::now().timestamp();
+        let mut queue = self.queue();
+
+        for path in paths {
+            // Mirrors `UNIQUE(path, session_id)` with `INSERT OR IGNORE`
+            if !queue.queued.insert((session_id.to_string(), path.clone())) {
+                continue;
+            }
+            queue.next_id += 1;
+            let id = queue.next_id;
+            queue.items.insert(
+                id,
+                WorkItem {
+                    id: Some(id),
+                    path: path.clone(),
+                    discovered_at: now,
+                    priority,
+                    session_id: session_id.to_string(),
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn peek_work_batch(&self, session_id: &str, batch_size: usize) -> Result<Vec<WorkItem>> {
+        let queue = self.queue();
+        let mut items: Vec<WorkItem> = queue
+            .items
+            .values()
+            .filter(|item| item.session_id == session_id)
+            .cloned()
+            .collect();
+
+        // Same order as `ORDER BY priority DESC, id ASC`
+        items.sort_by_key(|item| (std::cmp::Reverse(item.priority), item.id));
+        items.truncate(batch_size);
+        Ok(items)
+    }
+
+    async fn remove_work_items(&self, item_ids: &[i64]) -> Result<()> {
+        let mut queue = self.queue();
+        for id in item_ids {
+            if let Some(item) = queue.items.remove(id) {
+                queue.queued.remove(&(item.session_id, item.path));
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_work_count(&self, session_id: &str) -> Result<usize> {
+        Ok(self
+            .queue()
+            .items
+            .values()
+            .filter(|item| item.session_id == session_id)
+            .count())
+    }
+
+    async fn save_hits(&self, session_id: &str, hits: &[Hit]) -> Result<()> {
+        let mut found = self.found();
+        let session_files = found.entry(session_id.to_string()).or_default();
+        let mut seen: HashMap<PathBuf, usize> = session_files
+            .iter()
+            .enumerate()
+            .map(|(index, hit)| (hit.path.clone(), index))
+            .collect();
+
+        for hit in hits {
+            match seen.get(&hit.path) {
+                Some(&index) => {
+                    let existing = &mut session_files[index];
+                    existing.size = existing.size.or(hit.size);
+                }
+                None => {
+                    seen.insert(hit.path.clone(), session_files.len());
+                    session_files.push(hit.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_hits(&self, session_id: &str) -> Result<Vec<Hit>> {
+        Ok(self.found().get(session_id).cloned().unwrap_or_default())
+    }
+
+    async fn record_deletions(&self, records: &[DeletionRecord]) -> Result<()> {
+        self.deletions
+            .lock()
+            .expect("Failed to acquire lock on deletions")
+            .extend_from_slice(records);
+        Ok(())
+    }
+
+    async fn record_rejections(&self, _session_id: &str, files: &[PathBuf]) -> Result<()> {
+        self.rejections
+            .lock()
+            .expect("Failed to acquire lock on rejections")
+            .extend(files.iter().cloned());
+        Ok(())
+    }
+
+    async fn load_rejections(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .rejections
+            .lock()
+            .expect("Failed to acquire lock on rejections")
+            .iter()
+            .filter(|path| path.starts_with(root))
+            .cloned()
+            .collect())
+    }
+}
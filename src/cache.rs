@@ -6,11 +6,15 @@ use sqlx::{
     Row, SqlitePool,
 };
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use uuid::Uuid;
 
+use crate::migrations;
+use crate::portable::CacheRecord;
+use crate::roots;
+
 /// Represents the state of a directory in the cache
 /// Used for batch operations to minimize database round trips
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,13 +25,85 @@ pub struct DirectoryState {
     pub ds_store_found: bool,
     pub ds_store_deleted: bool,
     pub error_message: Option<String>,
+    /// Directory modification time (nanoseconds since the epoch) when it was searched
+    #[serde(default)]
+    pub mtime_ns: Option<i64>,
+    /// Directory inode when it was searched, where the platform has one
+    #[serde(default)]
+    pub inode: Option<i64>,
+}
+
+impl DirectoryState {
+    /// The modification stamp recorded for this directory
+    #[must_use]
+    pub fn stamp(&self) -> DirectoryStamp {
+        DirectoryStamp {
+            mtime_ns: self.mtime_ns,
+            inode: self.inode,
+        }
+    }
+}
+
+/// Identity of a directory's contents at the time it was searched
+///
+/// Adding or removing an entry (such as a new `.DS_Store`) bumps the directory mtime,
+/// and replacing the directory changes its inode, so a mismatch means a cached search
+/// result can no longer be trusted even if it is still inside the cache window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectoryStamp {
+    pub mtime_ns: Option<i64>,
+    pub inode: Option<i64>,
+}
+
+impl DirectoryStamp {
+    #[must_use]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        let mtime_ns = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|elapsed| i64::try_from(elapsed.as_nanos()).ok());
+
+        #[cfg(unix)]
+        let inode = {
+            use std::os::unix::fs::MetadataExt;
+            i64::try_from(metadata.ino()).ok()
+        };
+        #[cfg(not(unix))]
+        let inode = None;
+
+        Self { mtime_ns, inode }
+    }
+
+    /// Read the current stamp of a directory, or `None` if it cannot be inspected
+    #[must_use]
+    pub fn of(path: &Path) -> Option<Self> {
+        std::fs::metadata(path)
+            .ok()
+            .map(|metadata| Self::from_metadata(&metadata))
+    }
+
+    /// Whether `current` still describes the directory this stamp was taken from
+    ///
+    /// Fields that were never recorded (rows written before stamps existed, or
+    /// platforms without inodes) are not compared, leaving only the age check.
+    #[must_use]
+    pub fn matches(&self, current: &Self) -> bool {
+        fn same(recorded: Option<i64>, current: Option<i64>) -> bool {
+            match (recorded, current) {
+                (Some(recorded), Some(current)) => recorded == current,
+                _ => true,
+            }
+        }
+        same(self.mtime_ns, current.mtime_ns) && same(self.inode, current.inode)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DirectoryStatus {
     NotCached,  // Directory has never been searched
     Incomplete, // Directory search was started but not completed
-    Stale,      // Directory was searched but cache has expired
+    Stale,      // Directory was searched but cache has expired or the directory changed
     Fresh,      // Directory was recently searched and is still fresh
 }
 
@@ -40,7 +116,7 @@ pub struct WorkItem {
     pub session_id: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchSession {
     pub session_id: String,
     pub root_path: PathBuf,
@@ -49,9 +125,18 @@ pub struct SearchSession {
     pub is_recursive: bool,
     pub is_dry_run: bool,
     pub status: SearchSessionStatus,
+    /// Cleanup profiles the search ran with, as [`crate::profiles::ProfileSet::key`]
+    /// gives them; empty for a plain `.DS_Store` search
+    #[serde(default)]
+    pub profiles: String,
+    /// Every search directory when the session searched more than one, in which case
+    /// `root_path` is the directory holding them all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<PathBuf>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SearchSessionStatus {
     Active,
     Completed,
@@ -81,11 +166,245 @@ impl SearchSessionStatus {
     }
 }
 
+/// A `.DS_Store` file recorded against a search session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoundFile {
+    pub session_id: String,
+    pub file_path: PathBuf,
+    pub discovered_at: i64,
+    /// File size in bytes when it was found, if it could be read
+    #[serde(default)]
+    pub size: Option<i64>,
+}
+
+/// A `.DS_Store` file turned up by a search, with its size at the time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub size: Option<u64>,
+}
+
+impl Hit {
+    /// Look up the current size of `path`; a file that cannot be read has no size
+    #[must_use]
+    pub fn of(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            size: std::fs::symlink_metadata(path)
+                .ok()
+                .map(|metadata| metadata.len()),
+        }
+    }
+}
+
+/// What happened when `dds` tried to remove a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionOutcome {
+    Deleted,
+    /// The file was already gone by the time it was removed
+    Missing,
+    Failed,
+    /// An archive was rewritten without its macOS entries; the record describes the
+    /// archive as it was before
+    Rewritten,
+}
+
+impl DeletionOutcome {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeletionOutcome::Deleted => "deleted",
+            DeletionOutcome::Missing => "missing",
+            DeletionOutcome::Failed => "failed",
+            DeletionOutcome::Rewritten => "rewritten",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "deleted" => DeletionOutcome::Deleted,
+            "missing" => DeletionOutcome::Missing,
+            "rewritten" => DeletionOutcome::Rewritten,
+            _ => DeletionOutcome::Failed,
+        }
+    }
+}
+
+/// One attempted removal, as kept in the `deletions` audit table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletionRecord {
+    pub file_path: PathBuf,
+    pub size: Option<i64>,
+    /// Modification time of the file in seconds since the epoch
+    pub mtime: Option<i64>,
+    pub owner_uid: Option<i64>,
+    /// Hex-encoded SHA-256 of the file contents
+    pub content_hash: Option<String>,
+    pub session_id: Option<String>,
+    pub outcome: DeletionOutcome,
+    pub error_message: Option<String>,
+    pub attempted_at: i64,
+}
+
+impl DeletionRecord {
+    /// Capture what can still be known about `path` before it is removed
+    ///
+    /// Starts out as [`DeletionOutcome::Missing`] until [`Self::with_result`] fills in
+    /// what actually happened.
+    #[must_use]
+    pub fn capture(path: &Path, session_id: Option<&str>) -> Self {
+        use sha2::{Digest, Sha256};
+
+        let metadata = std::fs::symlink_metadata(path).ok();
+        let mtime = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|elapsed| i64::try_from(elapsed.as_secs()).ok());
+        // Streamed, since archives being rewritten can be far larger than memory
+        let content_hash = metadata
+            .as_ref()
+            .filter(|metadata| metadata.is_file())
+            .and_then(|_| {
+                let mut file = std::io::BufReader::new(std::fs::File::open(path).ok()?);
+                let mut hasher = Sha256::new();
+                std::io::copy(&mut file, &mut hasher).ok()?;
+                Some(format!("{:x}", hasher.finalize()))
+            });
+
+        Self {
+            file_path: path.to_path_buf(),
+            size: metadata
+                .as_ref()
+                .and_then(|metadata| i64::try_from(metadata.len()).ok()),
+            mtime,
+            owner_uid: metadata.as_ref().and_then(owner_uid),
+            content_hash,
+            session_id: session_id.map(str::to_string),
+            outcome: DeletionOutcome::Missing,
+            error_message: None,
+            attempted_at: Utc::now().timestamp(),
+        }
+    }
+
+    /// Record the result of the removal attempt
+    #[must_use]
+    pub fn with_result(mut self, result: &std::io::Result<()>) -> Self {
+        self.attempted_at = Utc::now().timestamp();
+        match result {
+            Ok(()) => self.outcome = DeletionOutcome::Deleted,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                self.outcome = DeletionOutcome::Missing;
+            }
+            Err(err) => {
+                self.outcome = DeletionOutcome::Failed;
+                self.error_message = Some(err.to_string());
+            }
+        }
+        self
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn owner_uid(metadata: &std::fs::Metadata) -> Option<i64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(i64::from(metadata.uid()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn owner_uid(_metadata: &std::fs::Metadata) -> Option<i64> {
+    None
+}
+
+/// Filters for [`Cache::get_deletions`]; fields left as `None` match everything
+#[derive(Debug, Clone, Default)]
+pub struct DeletionQuery {
+    /// Only files at or below this path
+    pub prefix: Option<PathBuf>,
+    /// Attempted at or after this Unix timestamp
+    pub since: Option<i64>,
+    /// Attempted strictly before this Unix timestamp
+    pub until: Option<i64>,
+    /// Only attempts made by this search session
+    pub session_id: Option<String>,
+    pub limit: Option<u64>,
+}
+
+/// Number of rows from an import that were written to the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub directories: u64,
+    pub sessions: u64,
+    pub found_files: u64,
+}
+
+/// How long an active session may go without a heartbeat before it counts as abandoned
+pub const SESSION_HEARTBEAT_TIMEOUT_SECS: i64 = 120;
+
+/// How often [`Cache::keep_alive`] records a heartbeat, well inside the timeout
+const SESSION_HEARTBEAT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Stops the background heartbeat started by [`Cache::keep_alive`] when dropped
+#[derive(Debug)]
+pub struct HeartbeatGuard(tokio::task::JoinHandle<()>);
+
+impl Drop for HeartbeatGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Whether the process that owns an active session is still around
+///
+/// Sessions written before owners were tracked never heartbeat and are treated as dead.
+/// A recent heartbeat alone is not enough when the recorded PID no longer exists, which
+/// catches crashed processes without waiting out the timeout.
+fn owner_is_alive(owner_pid: Option<i64>, heartbeat_at: Option<i64>, now: i64) -> bool {
+    let Some(heartbeat_at) = heartbeat_at else {
+        return false;
+    };
+    if now - heartbeat_at > SESSION_HEARTBEAT_TIMEOUT_SECS {
+        return false;
+    }
+    owner_pid.map_or(true, process_exists)
+}
+
+#[cfg(unix)]
+fn process_exists(pid: i64) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: signal 0 only performs the existence and permission checks; nothing is delivered
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_exists(_pid: i64) -> bool {
+    // Without a cheap liveness probe, rely on the heartbeat alone
+    true
+}
+
+/// Number of rows removed by [`Cache::forget_subtree`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ForgetSummary {
+    pub directories: u64,
+    pub sessions: u64,
+    pub found_files: u64,
+    pub rejections: u64,
+}
+
 pub struct Cache {
-    pub pool: SqlitePool,
+    pool: SqlitePool,
     // In-memory cache of recently searched directories for O(1) lookups
     // This avoids database queries for the most common case (already searched)
-    fresh_complete_dirs: HashSet<PathBuf>,
+    // The stamp recorded at search time is kept alongside to detect changed directories
+    fresh_complete_dirs: HashMap<PathBuf, DirectoryStamp>,
     window_hours: u64,
     force_refresh: bool,
     // Current search session for queue operations
@@ -138,117 +457,35 @@ impl Cache {
         // These pragmas improve query performance
         sqlx::query("PRAGMA optimize").execute(&pool).await?; // Optimize query planner statistics
 
-        // Check if we need to migrate from old schema
-        if Self::needs_migration(&pool).await? {
-            Self::migrate_schema(&pool).await?;
-        }
-
-        // Create new schema with work queue support
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS directory_cache (
-                path TEXT PRIMARY KEY,
-                last_searched_at INTEGER NOT NULL,
-                search_completed BOOLEAN NOT NULL DEFAULT FALSE,
-                ds_store_found BOOLEAN NOT NULL DEFAULT FALSE,
-                ds_store_deleted BOOLEAN NOT NULL DEFAULT FALSE,
-                error_message TEXT
-            )
-            ",
-        )
-        .execute(&pool)
-        .await?;
-
-        // Create work queue table for persistent queue management
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS work_queue (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                path TEXT NOT NULL,
-                discovered_at INTEGER NOT NULL,
-                priority INTEGER NOT NULL DEFAULT 0,
-                session_id TEXT,
-                UNIQUE(path, session_id)
-            )
-            ",
-        )
-        .execute(&pool)
-        .await?;
-
-        // Create search sessions table to track search progress
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS search_sessions (
-                session_id TEXT PRIMARY KEY,
-                root_path TEXT NOT NULL,
-                started_at INTEGER NOT NULL,
-                completed_at INTEGER,
-                is_recursive BOOLEAN NOT NULL,
-                is_dry_run BOOLEAN NOT NULL,
-                status TEXT NOT NULL DEFAULT 'active'
-            )
-            ",
-        )
-        .execute(&pool)
-        .await?;
-
-        // Create found files table to persist discovered .DS_Store files
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS found_files (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id TEXT NOT NULL,
-                file_path TEXT NOT NULL,
-                discovered_at INTEGER NOT NULL,
-                FOREIGN KEY (session_id) REFERENCES search_sessions(session_id),
-                UNIQUE(session_id, file_path)
-            )
-            ",
-        )
-        .execute(&pool)
-        .await?;
-
-        // Create indices for optimal query performance
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_last_searched ON directory_cache(last_searched_at)",
-        )
-        .execute(&pool)
-        .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_incomplete ON directory_cache(search_completed) WHERE search_completed = FALSE")
-            .execute(&pool)
-            .await?;
-        // Additional index for combined queries
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_fresh_complete ON directory_cache(last_searched_at, search_completed) WHERE search_completed = TRUE")
-            .execute(&pool)
-            .await?;
+        Self::from_pool(pool, window_hours, force).await
+    }
 
-        // Indices for work queue operations
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_queue_session ON work_queue(session_id, priority, id)",
-        )
-        .execute(&pool)
-        .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_queue_path ON work_queue(path)")
-            .execute(&pool)
+    /// Creates a cache that lives only in memory for the lifetime of this process
+    ///
+    /// Sessions, the work queue and found files behave exactly as with the on-disk
+    /// cache, but nothing is written to the filesystem. Useful for CI containers and
+    /// one-off scans where `~/.dds` should not be created.
+    pub async fn in_memory(window_hours: u64, force: bool) -> Result<Self> {
+        // Every connection to `:memory:` opens its own empty database, so the pool must
+        // hold exactly one connection and never let it be closed for idleness
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::from_str("sqlite::memory:")?)
             .await?;
 
-        // Indices for search sessions
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_sessions_status ON search_sessions(status, started_at)",
-        )
-        .execute(&pool)
-        .await?;
+        Self::from_pool(pool, window_hours, force).await
+    }
 
-        // Indices for found files
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_found_files_session ON found_files(session_id)",
-        )
-        .execute(&pool)
-        .await?;
+    async fn from_pool(pool: SqlitePool, window_hours: u64, force: bool) -> Result<Self> {
+        // Create or upgrade the schema to the version this build expects
+        migrations::run(&pool).await?;
 
         // Load fresh complete directories into memory
         let fresh_complete_dirs = if force {
-            HashSet::new()
+            HashMap::new()
         } else {
             Self::load_fresh_complete_dirs(&pool, window_hours).await?
         };
@@ -263,141 +500,37 @@ impl Cache {
 
         // Validate cache integrity on startup
         if let Err(e) = cache.validate_integrity().await {
-            eprintln!("Warning: Cache validation failed: {e}. Clearing cache.");
+            tracing::warn!(error = %e, "Cache validation failed, clearing cache");
             cache.clear_all().await?;
         }
 
         Ok(cache)
     }
 
-    async fn needs_migration(pool: &SqlitePool) -> Result<bool> {
-        // Check if old table exists
-        let result = sqlx::query(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name='searched_dirs'",
-        )
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(result.is_some())
+    /// Exact path and `/`-terminated prefix used to match a whole subtree in SQL
+    ///
+    /// Matching on `path = exact OR substr(path, 1, length(prefix)) = prefix` keeps `/foo`
+    /// from matching `/foobar`, which a bare `LIKE '/foo%'` would, and stays case-sensitive.
+    fn subtree_bounds(root: &Path) -> (String, String) {
+        let exact = Self::path_to_str(root).into_owned();
+        let prefix = if exact.ends_with(std::path::MAIN_SEPARATOR) {
+            exact.clone()
+        } else {
+            format!("{exact}{}", std::path::MAIN_SEPARATOR)
+        };
+        (exact, prefix)
     }
 
-    async fn migrate_schema(pool: &SqlitePool) -> Result<()> {
-        eprintln!("Migrating cache database to new schema with work queue support...");
-
-        // Begin transaction
-        let mut tx = pool.begin().await?;
-
-        // Create new directory_cache table
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS directory_cache (
-                path TEXT PRIMARY KEY,
-                last_searched_at INTEGER NOT NULL,
-                search_completed BOOLEAN NOT NULL DEFAULT TRUE,
-                ds_store_found BOOLEAN NOT NULL DEFAULT FALSE,
-                ds_store_deleted BOOLEAN NOT NULL DEFAULT FALSE,
-                error_message TEXT
-            )
-            ",
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Create work queue table
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS work_queue (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                path TEXT NOT NULL,
-                discovered_at INTEGER NOT NULL,
-                priority INTEGER NOT NULL DEFAULT 0,
-                session_id TEXT,
-                UNIQUE(path, session_id)
-            )
-            ",
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Create search sessions table
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS search_sessions (
-                session_id TEXT PRIMARY KEY,
-                root_path TEXT NOT NULL,
-                started_at INTEGER NOT NULL,
-                completed_at INTEGER,
-                is_recursive BOOLEAN NOT NULL,
-                is_dry_run BOOLEAN NOT NULL,
-                status TEXT NOT NULL DEFAULT 'active'
-            )
-            ",
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Create found files table
-        sqlx::query(
-            r"
-            CREATE TABLE IF NOT EXISTS found_files (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                session_id TEXT NOT NULL,
-                file_path TEXT NOT NULL,
-                discovered_at INTEGER NOT NULL,
-                FOREIGN KEY (session_id) REFERENCES search_sessions(session_id),
-                UNIQUE(session_id, file_path)
-            )
-            ",
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Copy data from old table
-        sqlx::query(
-            r"
-            INSERT INTO directory_cache (path, last_searched_at, search_completed)
-            SELECT path, last_searched_at, TRUE FROM searched_dirs
-            ",
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Drop old table
-        sqlx::query("DROP TABLE searched_dirs")
-            .execute(&mut *tx)
-            .await?;
-
-        // Create indices
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_queue_session ON work_queue(session_id, priority, id)",
-        )
-        .execute(&mut *tx)
-        .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_queue_path ON work_queue(path)")
-            .execute(&mut *tx)
-            .await?;
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_sessions_status ON search_sessions(status, started_at)",
-        )
-        .execute(&mut *tx)
-        .await?;
-        sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_found_files_session ON found_files(session_id)",
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        // Commit transaction
-        tx.commit().await?;
-
-        eprintln!("Migration completed successfully! Work queue support enabled.");
-        Ok(())
+    /// Parent directory string stored alongside each entry for child lookups
+    #[inline]
+    fn parent_to_str(path: &Path) -> Option<Cow<'_, str>> {
+        path.parent().map(Self::path_to_str)
     }
 
     async fn load_fresh_complete_dirs(
         pool: &SqlitePool,
         window_hours: u64,
-    ) -> Result<HashSet<PathBuf>> {
+    ) -> Result<HashMap<PathBuf, DirectoryStamp>> {
         let cutoff = Utc::now().timestamp()
             - i64::try_from(window_hours)
                 .unwrap_or(i64::MAX)
@@ -405,67 +538,40 @@ impl Cache {
 
         // Use the optimized index for this query
         let records = sqlx::query(
-            "SELECT path FROM directory_cache WHERE last_searched_at > ? AND search_completed = TRUE"
+            "SELECT path, dir_mtime_ns, dir_inode FROM directory_cache WHERE last_searched_at > ? AND search_completed = TRUE"
         )
         .bind(cutoff)
         .fetch_all(pool)
         .await?;
 
         // Pre-allocate with estimated capacity for better performance
-        let mut result = HashSet::with_capacity(records.len());
+        let mut result = HashMap::with_capacity(records.len());
         for row in records {
-            result.insert(PathBuf::from(row.get::<String, _>(0)));
+            let stamp = DirectoryStamp {
+                mtime_ns: row.get("dir_mtime_ns"),
+                inode: row.get("dir_inode"),
+            };
+            result.insert(PathBuf::from(row.get::<String, _>("path")), stamp);
         }
         Ok(result)
     }
 
+    /// Schema version recorded in the database
+    pub async fn schema_version(&self) -> Result<Option<i64>> {
+        migrations::current_version(&self.pool).await
+    }
+
     /// Fast O(1) check using in-memory cache
     /// This is the hot path for already-searched directories
     #[must_use]
     pub fn should_skip(&self, path: &Path) -> bool {
-        self.fresh_complete_dirs.contains(path)
+        self.fresh_complete_dirs.contains_key(path)
     }
 
     /// Determines if a directory should be searched
     /// Performance: First checks in-memory cache, then database if needed
     pub async fn should_search(&self, path: &Path) -> Result<bool> {
-        // If force refresh is enabled, always search
-        if self.force_refresh {
-            return Ok(true);
-        }
-
-        // First check in-memory cache (O(1) operation)
-        if self.fresh_complete_dirs.contains(path) {
-            return Ok(false);
-        }
-
-        // Check database for incomplete or old entries
-        let path_str = Self::path_to_str(path);
-        let cutoff = Utc::now().timestamp()
-            - i64::try_from(self.window_hours)
-                .unwrap_or(i64::MAX)
-                .saturating_mul(3600);
-
-        let result = sqlx::query(
-            r"
-            SELECT search_completed, last_searched_at
-            FROM directory_cache
-            WHERE path = ?
-            ",
-        )
-        .bind(path_str)
-        .fetch_optional(&self.pool)
-        .await?;
-
-        match result {
-            None => Ok(true), // Not in cache, should search
-            Some(row) => {
-                let search_completed: bool = row.get("search_completed");
-                let last_searched_at: i64 = row.get("last_searched_at");
-                // Should search if incomplete or stale
-                Ok(!search_completed || last_searched_at <= cutoff)
-            }
-        }
+        Ok(self.get_directory_status(path).await? != DirectoryStatus::Fresh)
     }
 
     pub async fn get_directory_status(&self, path: &Path) -> Result<DirectoryStatus> {
@@ -474,9 +580,9 @@ impl Cache {
             return Ok(DirectoryStatus::NotCached);
         }
 
-        // First check in-memory cache
-        if self.fresh_complete_dirs.contains(path) {
-            return Ok(DirectoryStatus::Fresh);
+        // First check in-memory cache, confirming the directory hasn't changed since
+        if let Some(recorded) = self.fresh_complete_dirs.get(path) {
+            return Ok(Self::status_from_stamp(recorded, path));
         }
 
         // Check database
@@ -488,7 +594,7 @@ impl Cache {
 
         let result = sqlx::query(
             r"
-            SELECT search_completed, last_searched_at
+            SELECT search_completed, last_searched_at, dir_mtime_ns, dir_inode
             FROM directory_cache
             WHERE path = ?
             ",
@@ -508,12 +614,44 @@ impl Cache {
                 } else if last_searched_at <= cutoff {
                     Ok(DirectoryStatus::Stale)
                 } else {
-                    Ok(DirectoryStatus::Fresh)
+                    let recorded = DirectoryStamp {
+                        mtime_ns: row.get("dir_mtime_ns"),
+                        inode: row.get("dir_inode"),
+                    };
+                    Ok(Self::status_from_stamp(&recorded, path))
                 }
             }
         }
     }
 
+    /// Fresh if the directory on disk still matches the stamp recorded when it was searched
+    pub(crate) fn status_from_stamp(recorded: &DirectoryStamp, path: &Path) -> DirectoryStatus {
+        // Nothing was recorded to compare against, so the age check alone decides
+        if *recorded == DirectoryStamp::default() {
+            return DirectoryStatus::Fresh;
+        }
+        match DirectoryStamp::of(path) {
+            Some(current) if recorded.matches(&current) => DirectoryStatus::Fresh,
+            _ => DirectoryStatus::Stale,
+        }
+    }
+
+    /// Subdirectories recorded under `path` by earlier searches
+    ///
+    /// An unchanged directory still has exactly these children, so the walker can descend
+    /// through it without reading it again and let each child check its own stamp.
+    pub async fn get_cached_children(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let rows = sqlx::query("SELECT path FROM directory_cache WHERE parent_path = ?")
+            .bind(Self::path_to_str(path).as_ref())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PathBuf::from(row.get::<String, _>("path")))
+            .collect())
+    }
+
     pub async fn mark_searched(&mut self, path: &Path) -> Result<()> {
         // This method is kept for backward compatibility
         // It marks a directory as completely searched
@@ -526,15 +664,17 @@ impl Cache {
 
         sqlx::query(
             r"
-            INSERT INTO directory_cache (path, last_searched_at, search_completed)
-            VALUES (?1, ?2, FALSE)
+            INSERT INTO directory_cache (path, last_searched_at, search_completed, parent_path)
+            VALUES (?1, ?2, FALSE, ?3)
             ON CONFLICT(path) DO UPDATE SET
                 last_searched_at = ?2,
-                search_completed = FALSE
+                search_completed = FALSE,
+                parent_path = excluded.parent_path
             ",
         )
         .bind(path_str.as_ref())
         .bind(now)
+        .bind(Self::parent_to_str(path))
         .execute(&self.pool)
         .await?;
 
@@ -552,31 +692,38 @@ impl Cache {
     ) -> Result<()> {
         let path_str = Self::path_to_str(path);
         let now = Utc::now().timestamp();
+        let stamp = DirectoryStamp::of(path).unwrap_or_default();
 
         sqlx::query(
             r"
             INSERT INTO directory_cache (
                 path, last_searched_at, search_completed,
-                ds_store_found, ds_store_deleted
+                ds_store_found, ds_store_deleted, dir_mtime_ns, dir_inode, parent_path
             )
-            VALUES (?1, ?2, TRUE, ?3, ?4)
+            VALUES (?1, ?2, TRUE, ?3, ?4, ?5, ?6, ?7)
             ON CONFLICT(path) DO UPDATE SET
                 last_searched_at = ?2,
                 search_completed = TRUE,
                 ds_store_found = ?3,
                 ds_store_deleted = ?4,
-                error_message = NULL
+                error_message = NULL,
+                dir_mtime_ns = ?5,
+                dir_inode = ?6,
+                parent_path = excluded.parent_path
             ",
         )
         .bind(path_str.as_ref())
         .bind(now)
         .bind(ds_store_found)
         .bind(ds_store_deleted)
+        .bind(stamp.mtime_ns)
+        .bind(stamp.inode)
+        .bind(Self::parent_to_str(path))
         .execute(&self.pool)
         .await?;
 
         // Update in-memory cache
-        self.fresh_complete_dirs.insert(path.to_path_buf());
+        self.fresh_complete_dirs.insert(path.to_path_buf(), stamp);
 
         Ok(())
     }
@@ -588,18 +735,20 @@ impl Cache {
         sqlx::query(
             r"
             INSERT INTO directory_cache (
-                path, last_searched_at, search_completed, error_message
+                path, last_searched_at, search_completed, error_message, parent_path
             )
-            VALUES (?1, ?2, TRUE, ?3)
+            VALUES (?1, ?2, TRUE, ?3, ?4)
             ON CONFLICT(path) DO UPDATE SET
                 last_searched_at = ?2,
                 search_completed = TRUE,
-                error_message = ?3
+                error_message = ?3,
+                parent_path = excluded.parent_path
             ",
         )
         .bind(path_str.as_ref())
         .bind(now)
         .bind(error)
+        .bind(Self::parent_to_str(path))
         .execute(&self.pool)
         .await?;
 
@@ -619,15 +768,17 @@ impl Cache {
             let path_str = Self::path_to_str(path);
             sqlx::query(
                 r"
-                INSERT INTO directory_cache (path, last_searched_at, search_completed)
-                VALUES (?1, ?2, FALSE)
+                INSERT INTO directory_cache (path, last_searched_at, search_completed, parent_path)
+                VALUES (?1, ?2, FALSE, ?3)
                 ON CONFLICT(path) DO UPDATE SET
                     last_searched_at = ?2,
-                    search_completed = FALSE
+                    search_completed = FALSE,
+                    parent_path = excluded.parent_path
                 ",
             )
             .bind(path_str.as_ref())
             .bind(now)
+            .bind(Self::parent_to_str(path))
             .execute(&mut *tx)
             .await?;
 
@@ -656,15 +807,19 @@ impl Cache {
                     r"
                     INSERT INTO directory_cache (
                         path, last_searched_at, search_completed,
-                        ds_store_found, ds_store_deleted, error_message
+                        ds_store_found, ds_store_deleted, error_message,
+                        dir_mtime_ns, dir_inode, parent_path
                     )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
                     ON CONFLICT(path) DO UPDATE SET
                         last_searched_at = ?2,
                         search_completed = ?3,
                         ds_store_found = ?4,
                         ds_store_deleted = ?5,
-                        error_message = ?6
+                        error_message = ?6,
+                        dir_mtime_ns = ?7,
+                        dir_inode = ?8,
+                        parent_path = excluded.parent_path
                     ",
                 )
                 .bind(path_str.as_ref())
@@ -673,12 +828,16 @@ impl Cache {
                 .bind(state.ds_store_found)
                 .bind(state.ds_store_deleted)
                 .bind(&state.error_message)
+                .bind(state.mtime_ns)
+                .bind(state.inode)
+                .bind(Self::parent_to_str(&state.path))
                 .execute(&mut *tx)
                 .await?;
 
                 // Update in-memory cache if completed
                 if state.search_completed {
-                    self.fresh_complete_dirs.insert(state.path.clone());
+                    self.fresh_complete_dirs
+                        .insert(state.path.clone(), state.stamp());
                 }
             }
 
@@ -734,6 +893,17 @@ impl Cache {
         })
     }
 
+    /// Size of the cache database in bytes, not counting any uncheckpointed WAL
+    pub async fn database_size_bytes(&self) -> Result<u64> {
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(u64::try_from(page_count.saturating_mul(page_size)).unwrap_or(0))
+    }
+
     pub async fn clear_incomplete(&self) -> Result<u64> {
         let result = sqlx::query("DELETE FROM directory_cache WHERE search_completed = FALSE")
             .execute(&self.pool)
@@ -822,7 +992,10 @@ impl Cache {
 
         let count: i64 = inconsistent_count.get("count");
         if count > 0 {
-            eprintln!("Warning: Found {count} directories marked as deleted without being found");
+            tracing::warn!(
+                count,
+                "Found directories marked as deleted without being found"
+            );
             // Fix the inconsistency
             sqlx::query(
                 "UPDATE directory_cache SET ds_store_deleted = FALSE
@@ -835,6 +1008,92 @@ impl Cache {
         Ok(())
     }
 
+    /// Drop everything the cache knows about `root` and the directories below it
+    ///
+    /// Removes `directory_cache` rows and found files under the prefix, along with any
+    /// sessions rooted there. Cached ancestors are expired so the next walk reads them
+    /// again and rediscovers the forgotten subtree instead of pruning it.
+    pub async fn forget_subtree(&mut self, root: &Path) -> Result<ForgetSummary> {
+        // Normalise away trailing separators so `/foo/` and `/foo` forget the same rows
+        let root: PathBuf = root.components().collect();
+        let (exact, prefix) = Self::subtree_bounds(&root);
+        let mut summary = ForgetSummary::default();
+        let mut tx = self.pool.begin().await?;
+
+        let sessions: Vec<String> = sqlx::query(
+            "SELECT session_id FROM search_sessions
+             WHERE root_path = ?1 OR substr(root_path, 1, length(?2)) = ?2",
+        )
+        .bind(&exact)
+        .bind(&prefix)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| row.get("session_id"))
+        .collect();
+
+        for session_id in &sessions {
+            sqlx::query("DELETE FROM work_queue WHERE session_id = ?")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+            summary.found_files += sqlx::query("DELETE FROM found_files WHERE session_id = ?")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            summary.sessions += sqlx::query("DELETE FROM search_sessions WHERE session_id = ?")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+
+        // Files under the prefix that were recorded by sessions rooted higher up
+        summary.found_files += sqlx::query(
+            "DELETE FROM found_files
+             WHERE file_path = ?1 OR substr(file_path, 1, length(?2)) = ?2",
+        )
+        .bind(&exact)
+        .bind(&prefix)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        summary.rejections = sqlx::query(
+            "DELETE FROM rejections WHERE file_path = ?1 OR substr(file_path, 1, length(?2)) = ?2",
+        )
+        .bind(&exact)
+        .bind(&prefix)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        summary.directories = sqlx::query(
+            "DELETE FROM directory_cache WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2",
+        )
+        .bind(&exact)
+        .bind(&prefix)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        for ancestor in root.ancestors().skip(1) {
+            sqlx::query("UPDATE directory_cache SET last_searched_at = 0 WHERE path = ?")
+                .bind(Self::path_to_str(ancestor).as_ref())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        self.fresh_complete_dirs.retain(|path, _| {
+            !path.starts_with(&root) && !root.ancestors().skip(1).any(|a| a == path)
+        });
+
+        Ok(summary)
+    }
+
     /// Clear all cache entries
     pub async fn clear_all(&mut self) -> Result<()> {
         sqlx::query("DELETE FROM directory_cache")
@@ -858,45 +1117,56 @@ impl Cache {
 
     // ===== WORK QUEUE OPERATIONS =====
 
-    /// Start a new search session
+    /// Start a new search session over one or more search directories
     pub async fn start_session(
         &mut self,
-        root_path: &Path,
+        roots: &[PathBuf],
         is_recursive: bool,
         is_dry_run: bool,
+        profiles: &str,
     ) -> Result<String> {
         let session_id = Uuid::new_v4().to_string();
         let now = Utc::now().timestamp();
+        let root_path = roots::common_ancestor(roots);
+        let roots_key = roots::session_key(roots);
 
         // Clean up any incomplete sessions first
         self.cleanup_stale_sessions().await?;
 
         sqlx::query(
             r"
-            INSERT INTO search_sessions (session_id, root_path, started_at, is_recursive, is_dry_run, status)
-            VALUES (?1, ?2, ?3, ?4, ?5, 'active')
-            "
+            INSERT INTO search_sessions (
+                session_id, root_path, started_at, is_recursive, is_dry_run, status,
+                owner_pid, heartbeat_at, profiles, roots
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6, ?3, ?7, ?8)
+            ",
         )
         .bind(&session_id)
-        .bind(Self::path_to_str(root_path).as_ref())
+        .bind(Self::path_to_str(&root_path).as_ref())
         .bind(now)
         .bind(is_recursive)
         .bind(is_dry_run)
+        .bind(i64::from(std::process::id()))
+        .bind(profiles)
+        .bind(&roots_key)
         .execute(&self.pool)
         .await?;
 
         self.current_session = Some(SearchSession {
             session_id: session_id.clone(),
-            root_path: root_path.to_path_buf(),
+            root_path,
             started_at: now,
             completed_at: None,
             is_recursive,
             is_dry_run,
             status: SearchSessionStatus::Active,
+            profiles: profiles.to_string(),
+            roots: roots::from_session_key(&roots_key),
         });
 
-        // Add root directory to work queue
-        self.enqueue_work(&session_id, root_path, 0).await?;
+        // Add the root directories to the work queue
+        self.enqueue_work_batch(&session_id, roots, 0).await?;
 
         Ok(session_id)
     }
@@ -936,30 +1206,107 @@ impl Cache {
         Ok(())
     }
 
-    /// Resume an interrupted session
+    /// Record that this process is still working on the current session
+    ///
+    /// Other processes treat an active session whose heartbeat is older than
+    /// [`SESSION_HEARTBEAT_TIMEOUT_SECS`] as abandoned, so the walker calls this regularly.
+    pub async fn heartbeat(&self) -> Result<()> {
+        if let Some(session) = &self.current_session {
+            Self::beat(&self.pool, &session.session_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Keep recording heartbeats for the current session from a background task
+    ///
+    /// Deleting, reviewing and cleaning up after the search can outlast the timeout
+    /// without ever returning to the walker, so they hold on to the session this way.
+    /// The task stops when the returned guard is dropped.
+    #[must_use]
+    pub fn keep_alive(&self) -> Option<HeartbeatGuard> {
+        let session_id = self.current_session.as_ref()?.session_id.clone();
+        let pool = self.pool.clone();
+        Some(HeartbeatGuard(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SESSION_HEARTBEAT_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::beat(&pool, &session_id).await {
+                    tracing::warn!(error = %e, session_id, "Failed to record session heartbeat");
+                }
+            }
+        })))
+    }
+
+    async fn beat(pool: &SqlitePool, session_id: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE search_sessions SET heartbeat_at = ?1 WHERE session_id = ?2 AND owner_pid = ?3",
+        )
+        .bind(Utc::now().timestamp())
+        .bind(session_id)
+        .bind(i64::from(std::process::id()))
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Resume an interrupted session, or one whose owning process has died
+    ///
+    /// Fails instead of resuming if another live `dds` process still owns an active
+    /// session for the same search, so two processes never walk one queue.
     pub async fn resume_session(
         &mut self,
-        root_path: &Path,
+        roots: &[PathBuf],
         is_recursive: bool,
         is_dry_run: bool,
+        profiles: &str,
     ) -> Result<Option<String>> {
-        // Look for interrupted sessions for this root path
-        let session_row = sqlx::query(
+        let now = Utc::now().timestamp();
+        let root_path = roots::common_ancestor(roots);
+        let roots_key = roots::session_key(roots);
+
+        // Look for unfinished sessions for this root path, newest first
+        let session_rows = sqlx::query(
             r"
-            SELECT session_id, started_at FROM search_sessions
-            WHERE root_path = ? AND status = 'interrupted' AND is_recursive = ? AND is_dry_run = ?
-            ORDER BY started_at DESC LIMIT 1
+            SELECT session_id, started_at, status, owner_pid, heartbeat_at FROM search_sessions
+            WHERE root_path = ? AND status IN ('interrupted', 'active')
+              AND is_recursive = ? AND is_dry_run = ? AND profiles = ? AND roots = ?
+            ORDER BY started_at DESC
             ",
         )
-        .bind(Self::path_to_str(root_path).as_ref())
+        .bind(Self::path_to_str(&root_path).as_ref())
         .bind(is_recursive)
         .bind(is_dry_run)
-        .fetch_optional(&self.pool)
+        .bind(profiles)
+        .bind(&roots_key)
+        .fetch_all(&self.pool)
         .await?;
 
-        if let Some(row) = session_row {
+        let mut candidate = None;
+        for row in &session_rows {
+            let status = SearchSessionStatus::parse(row.get::<&str, _>("status"));
+            let owner_pid: Option<i64> = row.get("owner_pid");
+            let heartbeat_at: Option<i64> = row.get("heartbeat_at");
+
+            if status == SearchSessionStatus::Active && owner_is_alive(owner_pid, heartbeat_at, now)
+            {
+                return Err(color_eyre::eyre::eyre!(
+                    "Search session {} for {} is still running in another dds process (pid {}). \
+                     Wait for it to finish or stop it before starting another search of the same directory.",
+                    row.get::<String, _>("session_id"),
+                    root_path.display(),
+                    owner_pid.map_or_else(|| "unknown".to_string(), |pid| pid.to_string())
+                ));
+            }
+            if candidate.is_none() {
+                candidate = Some(row);
+            }
+        }
+
+        if let Some(row) = candidate {
             let session_id: String = row.get("session_id");
             let started_at: i64 = row.get("started_at");
+            let status: String = row.get("status");
+            let heartbeat_at: Option<i64> = row.get("heartbeat_at");
 
             // Check if there's work remaining
             let work_count: i64 =
@@ -978,20 +1325,41 @@ impl Cache {
                     .get("count");
 
             if work_count > 0 || found_files_count > 0 {
-                // Resume this session - either work remaining or found files to load
-                sqlx::query("UPDATE search_sessions SET status = 'active' WHERE session_id = ?")
-                    .bind(&session_id)
-                    .execute(&self.pool)
-                    .await?;
+                // Claim the session only if nobody else changed it since we looked,
+                // so two processes racing to resume cannot both take it over
+                let claimed = sqlx::query(
+                    r"
+                    UPDATE search_sessions
+                    SET status = 'active', owner_pid = ?1, heartbeat_at = ?2
+                    WHERE session_id = ?3 AND status = ?4 AND heartbeat_at IS ?5
+                    ",
+                )
+                .bind(i64::from(std::process::id()))
+                .bind(now)
+                .bind(&session_id)
+                .bind(&status)
+                .bind(heartbeat_at)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+                if claimed == 0 {
+                    return Err(color_eyre::eyre::eyre!(
+                        "Search session {session_id} for {} was claimed by another dds process",
+                        root_path.display()
+                    ));
+                }
 
                 self.current_session = Some(SearchSession {
                     session_id: session_id.clone(),
-                    root_path: root_path.to_path_buf(),
+                    root_path,
                     started_at,
                     completed_at: None,
                     is_recursive,
                     is_dry_run,
                     status: SearchSessionStatus::Active,
+                    profiles: profiles.to_string(),
+                    roots: roots::from_session_key(&roots_key),
                 });
 
                 return Ok(Some(session_id));
@@ -1118,21 +1486,34 @@ impl Cache {
     }
 
     /// Clean up stale sessions (older than cache window)
+    ///
+    /// Sessions still owned by a live process are left alone whatever their age, since
+    /// another `dds` may be walking them right now against the same database.
     async fn cleanup_stale_sessions(&self) -> Result<()> {
-        let cutoff = Utc::now().timestamp()
+        let now = Utc::now().timestamp();
+        let cutoff = now
             - i64::try_from(self.window_hours)
                 .unwrap_or(i64::MAX)
                 .saturating_mul(3600);
 
         // Find stale sessions
         let stale_sessions = sqlx::query(
-            "SELECT session_id FROM search_sessions WHERE started_at < ? AND status != 'completed'",
+            r"
+            SELECT session_id, status, owner_pid, heartbeat_at FROM search_sessions
+            WHERE started_at < ? AND status != 'completed'
+            ",
         )
         .bind(cutoff)
         .fetch_all(&self.pool)
         .await?;
 
         for row in stale_sessions {
+            let status = SearchSessionStatus::parse(row.get::<&str, _>("status"));
+            if status == SearchSessionStatus::Active
+                && owner_is_alive(row.get("owner_pid"), row.get("heartbeat_at"), now)
+            {
+                continue;
+            }
             let session_id: String = row.get("session_id");
             self.cleanup_session(&session_id).await?;
         }
@@ -1140,6 +1521,41 @@ impl Cache {
         Ok(())
     }
 
+    /// Sessions currently being worked on by a live `dds` process
+    pub async fn get_live_sessions(&self) -> Result<Vec<(SearchSession, Option<i64>)>> {
+        let now = Utc::now().timestamp();
+        let rows = sqlx::query(
+            r"
+            SELECT session_id, root_path, started_at, is_recursive, is_dry_run, owner_pid,
+                heartbeat_at, profiles, roots
+            FROM search_sessions
+            WHERE status = 'active'
+            ORDER BY started_at ASC
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter(|row| owner_is_alive(row.get("owner_pid"), row.get("heartbeat_at"), now))
+            .map(|row| {
+                let session = SearchSession {
+                    session_id: row.get("session_id"),
+                    root_path: PathBuf::from(row.get::<String, _>("root_path")),
+                    started_at: row.get("started_at"),
+                    completed_at: None,
+                    is_recursive: row.get("is_recursive"),
+                    is_dry_run: row.get("is_dry_run"),
+                    status: SearchSessionStatus::Active,
+                    profiles: row.get("profiles"),
+                    roots: roots::from_session_key(row.get("roots")),
+                };
+                (session, row.get("owner_pid"))
+            })
+            .collect())
+    }
+
     /// Clean up a specific session
     async fn cleanup_session(&self, session_id: &str) -> Result<()> {
         sqlx::query("DELETE FROM work_queue WHERE session_id = ?")
@@ -1167,23 +1583,41 @@ impl Cache {
 
     /// Save found .DS_Store files for session
     pub async fn save_found_files(&self, session_id: &str, files: &[PathBuf]) -> Result<()> {
-        if files.is_empty() {
+        let hits: Vec<Hit> = files
+            .iter()
+            .map(|path| Hit {
+                path: path.clone(),
+                size: None,
+            })
+            .collect();
+        self.save_hits(session_id, &hits).await
+    }
+
+    /// Save found .DS_Store files and their sizes for session
+    ///
+    /// Files already recorded for the session keep their original discovery time, but
+    /// pick up a size if they did not have one yet.
+    pub async fn save_hits(&self, session_id: &str, hits: &[Hit]) -> Result<()> {
+        if hits.is_empty() {
             return Ok(());
         }
 
         let now = Utc::now().timestamp();
         let mut tx = self.pool.begin().await?;
 
-        for file_path in files {
+        for hit in hits {
             sqlx::query(
                 r"
-                INSERT OR IGNORE INTO found_files (session_id, file_path, discovered_at)
-                VALUES (?1, ?2, ?3)
+                INSERT INTO found_files (session_id, file_path, discovered_at, size)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT(session_id, file_path)
+                DO UPDATE SET size = COALESCE(found_files.size, excluded.size)
                 ",
             )
             .bind(session_id)
-            .bind(Self::path_to_str(file_path).as_ref())
+            .bind(Self::path_to_str(&hit.path).as_ref())
             .bind(now)
+            .bind(hit.size.and_then(|size| i64::try_from(size).ok()))
             .execute(&mut *tx)
             .await?;
         }
@@ -1194,8 +1628,18 @@ impl Cache {
 
     /// Load found .DS_Store files for session
     pub async fn load_found_files(&self, session_id: &str) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .load_hits(session_id)
+            .await?
+            .into_iter()
+            .map(|hit| hit.path)
+            .collect())
+    }
+
+    /// Load found .DS_Store files and their recorded sizes for session
+    pub async fn load_hits(&self, session_id: &str) -> Result<Vec<Hit>> {
         let rows = sqlx::query(
-            "SELECT file_path FROM found_files WHERE session_id = ? ORDER BY discovered_at ASC",
+            "SELECT file_path, size FROM found_files WHERE session_id = ? ORDER BY discovered_at ASC, id ASC",
         )
         .bind(session_id)
         .fetch_all(&self.pool)
@@ -1203,7 +1647,12 @@ impl Cache {
 
         Ok(rows
             .into_iter()
-            .map(|row| PathBuf::from(row.get::<String, _>("file_path")))
+            .map(|row| Hit {
+                path: PathBuf::from(row.get::<String, _>("file_path")),
+                size: row
+                    .get::<Option<i64>, _>("size")
+                    .and_then(|size| u64::try_from(size).ok()),
+            })
             .collect())
     }
 
@@ -1259,11 +1708,12 @@ impl Cache {
                 WHERE dc.ds_store_found = TRUE
                   AND dc.ds_store_deleted = FALSE
                   AND dc.search_completed = TRUE
-                  AND dc.path LIKE ?1 || '%'
+                  AND (dc.path = ?1 OR substr(dc.path, 1, length(?2)) = ?2)
                 ORDER BY file_path
                 ",
             )
             .bind(root_path_str.as_ref())
+            .bind(Self::subtree_bounds(root_path).1)
         } else {
             // For non-recursive searches, only get the .DS_Store directly in the root path
             sqlx::query(
@@ -1287,6 +1737,391 @@ impl Cache {
             .map(|row| PathBuf::from(row.get::<String, _>("file_path")))
             .collect())
     }
+
+    // ===== DELETION AUDIT LOG =====
+
+    /// Append attempted removals to the `deletions` audit table
+    ///
+    /// The audit log is kept independently of the cache: clearing or forgetting cached
+    /// directories leaves it untouched.
+    pub async fn record_deletions(&self, records: &[DeletionRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for record in records {
+            sqlx::query(
+                r"
+                INSERT INTO deletions (
+                    file_path, size, mtime, owner_uid, content_hash,
+                    session_id, outcome, error_message, attempted_at
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ",
+            )
+            .bind(Self::path_to_str(&record.file_path).as_ref())
+            .bind(record.size)
+            .bind(record.mtime)
+            .bind(record.owner_uid)
+            .bind(&record.content_hash)
+            .bind(&record.session_id)
+            .bind(record.outcome.as_str())
+            .bind(&record.error_message)
+            .bind(record.attempted_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Audit log entries matching `query`, newest first
+    pub async fn get_deletions(&self, query: &DeletionQuery) -> Result<Vec<DeletionRecord>> {
+        let (exact, prefix) = match &query.prefix {
+            Some(root) => {
+                let (exact, prefix) = Self::subtree_bounds(&root.components().collect::<PathBuf>());
+                (Some(exact), Some(prefix))
+            }
+            None => (None, None),
+        };
+
+        let rows = sqlx::query(
+            r"
+            SELECT file_path, size, mtime, owner_uid, content_hash,
+                   session_id, outcome, error_message, attempted_at
+            FROM deletions
+            WHERE (?1 IS NULL OR file_path = ?1 OR substr(file_path, 1, length(?2)) = ?2)
+              AND (?3 IS NULL OR attempted_at >= ?3)
+              AND (?4 IS NULL OR attempted_at < ?4)
+              AND (?6 IS NULL OR session_id = ?6)
+            ORDER BY attempted_at DESC, id DESC
+            LIMIT ?5
+            ",
+        )
+        .bind(exact)
+        .bind(prefix)
+        .bind(query.since)
+        .bind(query.until)
+        // SQLite treats a negative limit as no limit at all
+        .bind(
+            query
+                .limit
+                .map_or(-1, |limit| i64::try_from(limit).unwrap_or(i64::MAX)),
+        )
+        .bind(query.session_id.as_deref())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DeletionRecord {
+                file_path: PathBuf::from(row.get::<String, _>("file_path")),
+                size: row.get("size"),
+                mtime: row.get("mtime"),
+                owner_uid: row.get("owner_uid"),
+                content_hash: row.get("content_hash"),
+                session_id: row.get("session_id"),
+                outcome: DeletionOutcome::parse(row.get::<&str, _>("outcome")),
+                error_message: row.get("error_message"),
+                attempted_at: row.get("attempted_at"),
+            })
+            .collect())
+    }
+
+    // ===== INTERACTIVE REVIEW =====
+
+    /// Remember files the user chose to keep, so later runs leave them alone
+    pub async fn record_rejections(&self, session_id: &str, files: &[PathBuf]) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        for file_path in files {
+            sqlx::query(
+                r"
+                INSERT INTO rejections (file_path, session_id, rejected_at)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(file_path) DO UPDATE SET
+                    session_id = excluded.session_id,
+                    rejected_at = excluded.rejected_at
+                ",
+            )
+            .bind(Self::path_to_str(file_path).as_ref())
+            .bind(session_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Previously rejected files at or below `root`
+    pub async fn load_rejections(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        let (exact, prefix) = Self::subtree_bounds(root);
+        let rows = sqlx::query(
+            r"
+            SELECT file_path FROM rejections
+            WHERE file_path = ?1 OR substr(file_path, 1, length(?2)) = ?2
+            ORDER BY file_path ASC
+            ",
+        )
+        .bind(exact)
+        .bind(prefix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PathBuf::from(row.get::<String, _>("file_path")))
+            .collect())
+    }
+
+    // ===== HISTORY =====
+
+    /// Directories at or below `root` whose last search recorded an error
+    pub async fn get_error_directories(&self, root: &Path) -> Result<Vec<DirectoryState>> {
+        let (exact, prefix) = Self::subtree_bounds(root);
+        let rows = sqlx::query(
+            r"
+            SELECT path, last_searched_at, search_completed, ds_store_found, ds_store_deleted,
+                   error_message, dir_mtime_ns, dir_inode
+            FROM directory_cache
+            WHERE error_message IS NOT NULL
+              AND (path = ?1 OR substr(path, 1, length(?2)) = ?2)
+            ORDER BY path ASC
+            ",
+        )
+        .bind(exact)
+        .bind(prefix)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::directory_state_from_row).collect())
+    }
+
+    fn directory_state_from_row(row: &sqlx::sqlite::SqliteRow) -> DirectoryState {
+        DirectoryState {
+            path: PathBuf::from(row.get::<String, _>("path")),
+            last_searched_at: row.get("last_searched_at"),
+            search_completed: row.get("search_completed"),
+            ds_store_found: row.get("ds_store_found"),
+            ds_store_deleted: row.get("ds_store_deleted"),
+            error_message: row.get("error_message"),
+            mtime_ns: row.get("dir_mtime_ns"),
+            inode: row.get("dir_inode"),
+        }
+    }
+
+    /// Every recorded search session, oldest first
+    pub async fn get_sessions(&self) -> Result<Vec<SearchSession>> {
+        let rows = sqlx::query(
+            r"
+            SELECT session_id, root_path, started_at, completed_at, is_recursive, is_dry_run,
+                status, profiles, roots
+            FROM search_sessions
+            ORDER BY started_at ASC
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SearchSession {
+                session_id: row.get("session_id"),
+                root_path: PathBuf::from(row.get::<String, _>("root_path")),
+                started_at: row.get("started_at"),
+                completed_at: row.get("completed_at"),
+                is_recursive: row.get("is_recursive"),
+                is_dry_run: row.get("is_dry_run"),
+                status: SearchSessionStatus::parse(row.get::<&str, _>("status")),
+                profiles: row.get("profiles"),
+                roots: roots::from_session_key(row.get("roots")),
+            })
+            .collect())
+    }
+
+    /// Every found file across all sessions, in the order they were recorded
+    pub async fn get_found_history(&self) -> Result<Vec<FoundFile>> {
+        let rows = sqlx::query(
+            "SELECT session_id, file_path, discovered_at, size FROM found_files ORDER BY id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| FoundFile {
+                session_id: row.get("session_id"),
+                file_path: PathBuf::from(row.get::<String, _>("file_path")),
+                discovered_at: row.get("discovered_at"),
+                size: row.get("size"),
+            })
+            .collect())
+    }
+
+    // ===== PORTABLE EXPORT / IMPORT =====
+
+    /// Dump `search_sessions`, `directory_cache` and `found_files` as portable records
+    ///
+    /// Sessions come first so that an import can satisfy the `found_files` foreign key
+    /// while streaming the records back in order.
+    pub async fn export_records(&self) -> Result<Vec<CacheRecord>> {
+        let mut records: Vec<CacheRecord> = self
+            .get_sessions()
+            .await?
+            .into_iter()
+            .map(CacheRecord::SearchSessions)
+            .collect();
+
+        let directory_rows = sqlx::query(
+            r"
+            SELECT path, last_searched_at, search_completed, ds_store_found, ds_store_deleted,
+                   error_message, dir_mtime_ns, dir_inode
+            FROM directory_cache
+            ORDER BY path ASC
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        records.extend(
+            directory_rows
+                .iter()
+                .map(Self::directory_state_from_row)
+                .map(CacheRecord::DirectoryCache),
+        );
+
+        records.extend(
+            self.get_found_history()
+                .await?
+                .into_iter()
+                .map(CacheRecord::FoundFiles),
+        );
+
+        Ok(records)
+    }
+
+    /// Merge portable records into the cache
+    ///
+    /// Directory entries only overwrite local rows whose `last_searched_at` is older than
+    /// the incoming one, so importing a stale export never throws away newer local results.
+    /// Sessions are merged the same way on their latest timestamp, and sessions that were
+    /// still active on the exporting machine come in as interrupted so they can be resumed.
+    pub async fn import_records(&mut self, records: Vec<CacheRecord>) -> Result<ImportSummary> {
+        let mut sessions = Vec::new();
+        let mut directories = Vec::new();
+        let mut found = Vec::new();
+        for record in records {
+            match record {
+                CacheRecord::SearchSessions(session) => sessions.push(session),
+                CacheRecord::DirectoryCache(state) => directories.push(state),
+                CacheRecord::FoundFiles(file) => found.push(file),
+            }
+        }
+
+        let mut summary = ImportSummary::default();
+        let mut tx = self.pool.begin().await?;
+
+        for session in &sessions {
+            let status = match session.status {
+                SearchSessionStatus::Active => SearchSessionStatus::Interrupted,
+                ref other => other.clone(),
+            };
+            let result = sqlx::query(
+                r"
+                INSERT INTO search_sessions (
+                    session_id, root_path, started_at, completed_at,
+                    is_recursive, is_dry_run, status, profiles, roots
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT(session_id) DO UPDATE SET
+                    completed_at = excluded.completed_at,
+                    status = excluded.status
+                WHERE COALESCE(excluded.completed_at, excluded.started_at)
+                    > COALESCE(search_sessions.completed_at, search_sessions.started_at)
+                ",
+            )
+            .bind(&session.session_id)
+            .bind(Self::path_to_str(&session.root_path).as_ref())
+            .bind(session.started_at)
+            .bind(session.completed_at)
+            .bind(session.is_recursive)
+            .bind(session.is_dry_run)
+            .bind(status.as_str())
+            .bind(&session.profiles)
+            .bind(roots::session_key(&session.roots))
+            .execute(&mut *tx)
+            .await?;
+            summary.sessions += result.rows_affected();
+        }
+
+        for state in &directories {
+            let result = sqlx::query(
+                r"
+                INSERT INTO directory_cache (
+                    path, last_searched_at, search_completed,
+                    ds_store_found, ds_store_deleted, error_message,
+                    dir_mtime_ns, dir_inode, parent_path
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT(path) DO UPDATE SET
+                    last_searched_at = excluded.last_searched_at,
+                    search_completed = excluded.search_completed,
+                    ds_store_found = excluded.ds_store_found,
+                    ds_store_deleted = excluded.ds_store_deleted,
+                    error_message = excluded.error_message,
+                    dir_mtime_ns = excluded.dir_mtime_ns,
+                    dir_inode = excluded.dir_inode,
+                    parent_path = excluded.parent_path
+                WHERE excluded.last_searched_at > directory_cache.last_searched_at
+                ",
+            )
+            .bind(Self::path_to_str(&state.path).as_ref())
+            .bind(state.last_searched_at)
+            .bind(state.search_completed)
+            .bind(state.ds_store_found)
+            .bind(state.ds_store_deleted)
+            .bind(&state.error_message)
+            .bind(state.mtime_ns)
+            .bind(state.inode)
+            .bind(Self::parent_to_str(&state.path))
+            .execute(&mut *tx)
+            .await?;
+            summary.directories += result.rows_affected();
+        }
+
+        for file in &found {
+            let result = sqlx::query(
+                r"
+                INSERT OR IGNORE INTO found_files (session_id, file_path, discovered_at, size)
+                SELECT ?1, ?2, ?3, ?4
+                WHERE EXISTS (SELECT 1 FROM search_sessions WHERE session_id = ?1)
+                ",
+            )
+            .bind(&file.session_id)
+            .bind(Self::path_to_str(&file.file_path).as_ref())
+            .bind(file.discovered_at)
+            .bind(file.size)
+            .execute(&mut *tx)
+            .await?;
+            summary.found_files += result.rows_affected();
+        }
+
+        tx.commit().await?;
+
+        // Imported directories may now be fresh, so rebuild the in-memory lookup
+        if !self.force_refresh {
+            self.fresh_complete_dirs =
+                Self::load_fresh_complete_dirs(&self.pool, self.window_hours).await?;
+        }
+
+        Ok(summary)
+    }
 }
 
 #[derive(Debug, Clone)]
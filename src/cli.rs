@@ -1,4 +1,12 @@
-use clap::{ArgGroup, Parser};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use clap::{ArgGroup, Parser, Subcommand};
+
+use crate::filters::{parse_age, parse_size};
+use crate::portable::PortableFormat;
+use crate::report::{ReportFormat, TrendPeriod};
 
 /// A command line tool that deletes the `.DS_Store` system files commonly
 /// found around MacOS filesystems. Please note that Finder may behave differently
@@ -6,9 +14,10 @@ use clap::{ArgGroup, Parser};
 #[derive(Parser)]
 #[clap(name = "dds")]
 #[clap(version = "v0.2.0")]
+#[command(subcommand_precedence_over_arg = true)]
 #[clap(group(ArgGroup::new("operation")
     .args(&["cache_status", "cache_clear_incomplete", "cache_stats"])
-    .conflicts_with_all(&["recursive", "dry", "force", "dir"])))]
+    .conflicts_with_all(&["recursive", "dry", "force", "dir", "roots_from", "no_cache", "interactive", "max_delete", "owner", "group", "mine", "older_than", "newer_than", "min_size", "max_size", "appledouble", "appledouble_paired", "archives", "clean_archives", "archive_backup", "volume_cleanup", "confirm_volume_cleanup", "profile", "yes_really", "metrics_file", "usage", "tree", "report"])))]
 pub struct Cli {
     /// Increase the logging of detailed information as `dds` progresses
     #[arg(short, long, default_value_t = false)]
@@ -26,6 +35,93 @@ pub struct Cli {
     #[arg(short, long, default_value_t = false)]
     pub dry: bool,
 
+    /// Review the hits before anything is deleted, approving or rejecting them by directory
+    /// or by file. Rejected files are remembered and left alone by later runs until
+    /// `dds cache forget` is run on them.
+    #[arg(short, long, default_value_t = false, conflicts_with = "dry")]
+    pub interactive: bool,
+
+    /// Abort without deleting anything if more than N files would be deleted (overrides `max_delete` in config)
+    #[arg(long, value_name = "N")]
+    pub max_delete: Option<usize>,
+
+    /// Only delete files owned by this user (name or uid); may be repeated
+    #[arg(long, value_name = "USER")]
+    pub owner: Vec<String>,
+
+    /// Only delete files belonging to this group (name or gid); may be repeated
+    #[arg(long, value_name = "GROUP")]
+    pub group: Vec<String>,
+
+    /// Only delete files owned by the user running `dds`
+    #[arg(long, default_value_t = false, conflicts_with = "owner")]
+    pub mine: bool,
+
+    /// Only consider files last modified at least this long ago, e.g. `30d` or `12h`
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub older_than: Option<Duration>,
+
+    /// Only consider files last modified less than this long ago, e.g. `7d`
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub newer_than: Option<Duration>,
+
+    /// Ignore files smaller than this, e.g. `1K`
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub min_size: Option<u64>,
+
+    /// Ignore files larger than this, e.g. `1M`; oversized `.DS_Store` files are worth a look
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Also remove AppleDouble `._` files left behind after their data file was deleted.
+    /// Every directory is read again, since the cache only remembers `.DS_Store` files.
+    #[arg(long, default_value_t = false)]
+    pub appledouble: bool,
+
+    /// With `--appledouble`, also remove `._` files whose data file still exists, losing
+    /// the Finder tags and other metadata they hold
+    #[arg(long, default_value_t = false, requires = "appledouble")]
+    pub appledouble_paired: bool,
+
+    /// Look inside zip, tar, tar.gz and tar.zst archives and list the ones holding
+    /// `__MACOSX/`, `.DS_Store` or `._` entries. Every directory is read again, as with
+    /// `--appledouble`.
+    #[arg(long, default_value_t = false)]
+    pub archives: bool,
+
+    /// Rewrite the archives `--archives` lists without those entries; the original is
+    /// only replaced once the new archive is fully written
+    #[arg(long, default_value_t = false)]
+    pub clean_archives: bool,
+
+    /// Keep the original of every rewritten archive next to it as `<name>.bak`
+    #[arg(long, default_value_t = false, requires = "clean_archives")]
+    pub archive_backup: bool,
+
+    /// Treat the search directory as the root of a volume and offer to remove the
+    /// `.Spotlight-V100`, `.fseventsd`, `.Trashes` and `.TemporaryItems` directories there.
+    /// They are listed, with what they hold, and only removed once confirmed.
+    #[arg(long, default_value_t = false)]
+    pub volume_cleanup: bool,
+
+    /// Remove the directories `--volume-cleanup` lists without asking first
+    #[arg(long, default_value_t = false, requires = "volume_cleanup")]
+    pub confirm_volume_cleanup: bool,
+
+    /// Delete the files of a cleanup profile instead of only `.DS_Store`; may be repeated
+    ///
+    /// The built-in profiles are `mac` (`.DS_Store`, `._*`, `Icon\r`, `.localized`),
+    /// `windows` (`Thumbs.db`, `desktop.ini`, `ehthumbs.db`) and `editor` (`*.swp`, `*~`).
+    /// More can be defined under `[profiles]` in the config file. A `._` file is only
+    /// deleted if it holds AppleDouble data and, unless `--appledouble-paired` is given,
+    /// its data file is gone.
+    #[arg(long, value_name = "NAME")]
+    pub profile: Vec<String>,
+
+    /// Allow deleting under `/` or your home directory, which is refused otherwise
+    #[arg(long, default_value_t = false)]
+    pub yes_really: bool,
+
     /// Force refresh, ignoring cache
     #[arg(short = 'f', long, default_value_t = false)]
     pub force: bool,
@@ -34,6 +130,42 @@ pub struct Cli {
     #[arg(long)]
     pub cache_hours: Option<u64>,
 
+    /// Keep the cache in memory for this run only; nothing is written under `~/.dds`
+    #[arg(
+        long = "no-cache",
+        visible_alias = "ephemeral",
+        default_value_t = false
+    )]
+    pub no_cache: bool,
+
+    /// After the run, show how much space the `.DS_Store` files took, per top-level directory
+    #[arg(long, default_value_t = false)]
+    pub usage: bool,
+
+    /// After the run, show the hits as a directory tree with a count per subtree
+    #[arg(long, default_value_t = false)]
+    pub tree: bool,
+
+    /// How many levels below the search directory `--tree` shows
+    #[arg(long, value_name = "N", default_value_t = 3, requires = "tree")]
+    pub tree_depth: usize,
+
+    /// Leave out directories with fewer hits than this from `--tree`
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "tree")]
+    pub tree_min_hits: u64,
+
+    /// After the run, write a report of the session to FILE; FORMAT is `html` or `csv`
+    #[arg(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+    pub report: Option<Vec<String>>,
+
+    /// Write run metrics in OpenMetrics text format to this file (overrides `metrics_file` in config)
+    #[arg(long, value_name = "PATH")]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Also write diagnostics to rotating log files in this directory (overrides `log_dir` in config)
+    #[arg(long, value_name = "DIR")]
+    pub log_dir: Option<PathBuf>,
+
     /// Show information about incomplete searches and cache state
     #[arg(long, default_value_t = false)]
     pub cache_status: bool,
@@ -46,7 +178,126 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub cache_stats: bool,
 
-    /// The directory to search within for `.DS_Store` files
-    #[arg(default_value = ".")]
-    pub dir: String,
+    /// The directories to search within for `.DS_Store` files; the current directory
+    /// if none are given
+    ///
+    /// Several directories are searched in one session; with `--recursive`, a directory
+    /// inside another one given is only searched once.
+    pub dir: Vec<String>,
+
+    /// Also search the directories listed in FILE, one per line, or on stdin with `-`
+    ///
+    /// Blank lines and lines starting with `#` are skipped.
+    #[arg(long, value_name = "FILE")]
+    pub roots_from: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Manage the search cache database
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+
+    /// Show the audit log of attempted `.DS_Store` deletions and archive rewrites, newest first
+    Log {
+        /// Only show files at or below this path
+        path: Option<String>,
+
+        /// Only show deletions on or after this date (YYYY-MM-DD, UTC)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+
+        /// Only show deletions on or before this date (YYYY-MM-DD, UTC)
+        #[arg(long)]
+        until: Option<NaiveDate>,
+
+        /// Show at most this many entries
+        #[arg(short = 'n', long)]
+        limit: Option<u64>,
+    },
+
+    /// Summarise the search and deletion history kept in the cache
+    Report {
+        #[command(subcommand)]
+        action: ReportCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommand {
+    /// Hits found and deleted over time, and how quickly cleaned directories are re-infected
+    Trends {
+        /// Only count hits at or below this path
+        path: Option<String>,
+
+        /// Bucket the history by day or by week
+        #[arg(long, value_enum, default_value_t = TrendPeriod::Week)]
+        by: TrendPeriod,
+
+        /// How many of the most re-infected directories to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Write an HTML or CSV report of a stored session, for sharing and audits
+    Session {
+        /// The session to report on (defaults to the most recent one)
+        session: Option<String>,
+
+        /// HTML for reading, CSV with one row per hit for spreadsheets
+        #[arg(long, value_enum, default_value_t = ReportFormat::Html)]
+        format: ReportFormat,
+
+        /// Where to write the report (defaults to `dds-report-<session>.<format>`)
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+
+    /// Show a stored session's hits as a directory tree with a count per subtree
+    Tree {
+        /// The session to show (defaults to the most recent one)
+        session: Option<String>,
+
+        /// How many levels below the session's root to show
+        #[arg(long, default_value_t = 3)]
+        depth: usize,
+
+        /// Leave out directories with fewer hits than this
+        #[arg(long, default_value_t = 1)]
+        min_hits: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Write the directory cache, search sessions and found files to a portable file
+    Export {
+        /// Destination file, or `-` for stdout
+        file: PathBuf,
+
+        /// Output format (inferred from the file extension when omitted)
+        #[arg(long, value_enum)]
+        format: Option<PortableFormat>,
+    },
+
+    /// Remove cached results, found files and sessions for a directory and everything below it
+    Forget {
+        /// The directory whose cached results should be dropped
+        path: String,
+    },
+
+    /// Merge a previously exported file into the cache, keeping the newest entries
+    Import {
+        /// Source file, or `-` for stdin
+        file: PathBuf,
+
+        /// Input format (inferred from the file extension when omitted)
+        #[arg(long, value_enum)]
+        format: Option<PortableFormat>,
+    },
 }
@@ -1,27 +1,68 @@
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How often the log file in `log_dir` is rotated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     pub database_path: PathBuf,
     pub cache_window_hours: u64,
+    /// Write OpenMetrics for node_exporter's textfile collector here after every run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_file: Option<PathBuf>,
+    /// Also write diagnostics to rotating `dds.log` files in this directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub log_rotation: LogRotation,
+    /// `tracing` filter directives for the log file, such as `dds=debug,sqlx=warn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_filter: Option<String>,
+    /// `.DS_Store` files at or below these paths are never deleted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected_paths: Vec<PathBuf>,
+    /// Refuse to delete anything when a run would delete more files than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_delete: Option<usize>,
+    /// File name patterns to select with `--profile`, by profile name; a profile named
+    /// like a built-in one replaces it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Vec<String>>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        let home_dir = dirs::home_dir().expect("Could not determine home directory");
+fn home_dir() -> Result<PathBuf> {
+    dirs::home_dir().ok_or_else(|| eyre!("Could not determine home directory"))
+}
 
+impl Config {
+    /// The settings used when there is no config file, keeping the cache under `home_dir`
+    pub fn defaults(home_dir: &Path) -> Self {
         Self {
             database_path: home_dir.join(".dds").join("cache.sqlite"),
             cache_window_hours: 168, // 1 week
+            metrics_file: None,
+            log_dir: None,
+            log_rotation: LogRotation::default(),
+            log_filter: None,
+            protected_paths: Vec::new(),
+            max_delete: None,
+            profiles: BTreeMap::new(),
         }
     }
-}
 
-impl Config {
+    /// Read `~/.dds/config.toml`, creating it with defaults on first run
     pub async fn load() -> Result<Self> {
-        let home_dir = dirs::home_dir().expect("Could not determine home directory");
+        let home_dir = home_dir()?;
         let dds_dir = home_dir.join(".dds");
         let config_path = dds_dir.join("config.toml");
 
@@ -30,11 +71,24 @@ impl Config {
             Ok(toml::from_str(&contents)?)
         } else {
             // Create default config
-            let config = Self::default();
+            let config = Self::defaults(&home_dir);
             tokio::fs::create_dir_all(&dds_dir).await?;
             let contents = toml::to_string_pretty(&config)?;
             tokio::fs::write(&config_path, contents).await?;
             Ok(config)
         }
     }
+
+    /// Read `~/.dds/config.toml` if it exists, without creating anything on disk
+    pub async fn load_read_only() -> Result<Self> {
+        let home_dir = home_dir()?;
+        let config_path = home_dir.join(".dds").join("config.toml");
+
+        if config_path.exists() {
+            let contents = tokio::fs::read_to_string(&config_path).await?;
+            Ok(toml::from_str(&contents)?)
+        } else {
+            Ok(Self::defaults(&home_dir))
+        }
+    }
 }
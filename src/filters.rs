@@ -0,0 +1,98 @@
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime};
+
+/// Age and size limits a `.DS_Store` file has to meet before it counts as a hit
+///
+/// Limits left as `None` match everything. A file whose modification time cannot be
+/// read never passes an age limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HitFilter {
+    /// Only files last modified at least this long ago
+    pub older_than: Option<Duration>,
+    /// Only files last modified less than this long ago
+    pub newer_than: Option<Duration>,
+    /// Only files of at least this many bytes
+    pub min_size: Option<u64>,
+    /// Only files of at most this many bytes
+    pub max_size: Option<u64>,
+}
+
+impl HitFilter {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a file with this metadata passes every limit, as of `now`
+    #[must_use]
+    pub fn matches(&self, metadata: &Metadata, now: SystemTime) -> bool {
+        let size = metadata.len();
+        if self.min_size.is_some_and(|min| size < min)
+            || self.max_size.is_some_and(|max| size > max)
+        {
+            return false;
+        }
+
+        if self.older_than.is_none() && self.newer_than.is_none() {
+            return true;
+        }
+        // Files modified "in the future" by clock skew count as brand new
+        let Some(age) = metadata
+            .modified()
+            .ok()
+            .map(|modified| now.duration_since(modified).unwrap_or_default())
+        else {
+            return false;
+        };
+        self.older_than.map_or(true, |older_than| age >= older_than)
+            && self.newer_than.map_or(true, |newer_than| age < newer_than)
+    }
+}
+
+/// Parse an age such as `30d`, `12h`, `45m`, `90s` or `2w`; a bare number is in days
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("`{value}` is not an age like 30d, 12h or 45m"))?;
+
+    let seconds_per_unit = match unit.trim().to_ascii_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        other => return Err(format!("Unknown age unit `{other}`; use s, m, h, d or w")),
+    };
+    number
+        .checked_mul(seconds_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("`{value}` is too long an age"))
+}
+
+/// Parse a size such as `6148`, `16K`, `5M` or `1G`, using binary multiples
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("`{value}` is not a size like 6148, 16K or 5M"))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        other => return Err(format!("Unknown size unit `{other}`; use K, M or G")),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("`{value}` is too large a size"))
+}
@@ -0,0 +1,236 @@
+use std::fmt::Write as _;
+
+use crate::cache::{DeletionOutcome, DeletionRecord};
+use crate::report::{self, timestamp, HitTree, SessionReport};
+
+/// Levels of the hit tree that start out expanded
+const OPEN_DEPTH: usize = 2;
+
+const STYLE: &str = r"
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 70em; padding: 0 1em; color: #222; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ddd; padding-bottom: 0.2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #eee; vertical-align: top; }
+th { background: #f6f6f6; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.meta { color: #666; }
+.path { font-family: ui-monospace, Menlo, Consolas, monospace; word-break: break-all; }
+.tree ul { list-style: none; margin: 0; padding-left: 1.4em; }
+.tree summary { cursor: pointer; }
+.count { color: #666; margin-left: 0.4em; }
+.none { color: #666; font-style: italic; }
+";
+
+/// Render a single self-contained HTML page for a session
+///
+/// Styles are inlined and the tree uses `<details>` elements, so the page needs no
+/// scripts or external assets and can be opened offline or attached to an email.
+#[must_use]
+pub fn render(session_report: &SessionReport, generated_at: i64) -> String {
+    let SessionReport {
+        session,
+        summary,
+        deletions,
+        errors,
+    } = session_report;
+    let root = session.root_path.display().to_string();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(out, "<title>dds cleanup report: {}</title>", escape(&root));
+    let _ = writeln!(out, "<style>{STYLE}</style>\n</head>\n<body>");
+
+    let _ = writeln!(out, "<h1>.DS_Store cleanup report</h1>");
+    let _ = writeln!(
+        out,
+        "<p class=\"meta\"><span class=\"path\">{}</span><br>Session {} &middot; {} &middot; {}<br>Started {}{} &middot; report generated {}</p>",
+        escape(&root),
+        escape(&session.session_id),
+        if summary.dry_run { "dry run" } else { "deletion run" },
+        escape(session.status.as_str()),
+        timestamp(session.started_at),
+        session
+            .completed_at
+            .map(|completed_at| format!(", finished {}", timestamp(completed_at)))
+            .unwrap_or_default(),
+        timestamp(generated_at),
+    );
+
+    let _ = writeln!(out, "<h2>Summary</h2>\n<table>");
+    let rows: [(&str, String); 19] = [
+        (
+            "Directories searched",
+            summary.directories_searched.to_string(),
+        ),
+        (
+            "Directories resumed from an interrupted search",
+            summary.directories_resumed.to_string(),
+        ),
+        (
+            "Directories skipped (already cached)",
+            summary.directories_skipped.to_string(),
+        ),
+        (
+            "Directories with errors",
+            summary.directories_errored.to_string(),
+        ),
+        (".DS_Store files found", summary.hits_found.to_string()),
+        ("Space taken", report::format_bytes(summary.bytes_found)),
+        (
+            "Kept: outside the age or size limits",
+            summary.skipped_filtered.to_string(),
+        ),
+        (
+            "Kept: owned by someone else",
+            summary.skipped_ownership.to_string(),
+        ),
+        (
+            "Kept: AppleDouble file with its data file",
+            summary.skipped_paired.to_string(),
+        ),
+        (
+            "Kept: under a protected path",
+            summary.protected.to_string(),
+        ),
+        ("Kept: rejected in review", summary.rejected.to_string()),
+        ("Deleted", summary.deleted.to_string()),
+        ("Already gone", summary.missing.to_string()),
+        ("Failed to delete", summary.failed.to_string()),
+        (
+            "Archives with macOS entries",
+            summary.archives.len().to_string(),
+        ),
+        (
+            "Archives rewritten without them",
+            summary.archives_cleaned.to_string(),
+        ),
+        (
+            "Volume metadata directories removed",
+            format!(
+                "{} of {}",
+                summary.volume_dirs_removed,
+                summary.volume_dirs.len()
+            ),
+        ),
+        (
+            "Space reclaimed",
+            report::format_bytes(summary.bytes_reclaimed),
+        ),
+        (
+            "Duration",
+            report::format_duration(summary.duration.as_secs_f64()),
+        ),
+    ];
+    for (label, value) in rows {
+        let _ = writeln!(
+            out,
+            "<tr><th>{label}</th><td class=\"num\">{}</td></tr>",
+            escape(&value)
+        );
+    }
+    let _ = writeln!(out, "</table>");
+
+    let _ = writeln!(out, "<h2>Where the files were found</h2>");
+    if summary.hits.is_empty() {
+        let _ = writeln!(out, "<p class=\"none\">No .DS_Store files were found.</p>");
+    } else {
+        let tree = report::hit_tree(
+            &session.root_path,
+            summary.hits.iter().map(|hit| hit.path.as_path()),
+            None,
+            1,
+        );
+        let _ = writeln!(out, "<div class=\"tree\"><ul>");
+        tree_item(&mut out, &tree, 0);
+        let _ = writeln!(out, "</ul></div>");
+    }
+
+    let _ = writeln!(out, "<h2>Deletion failures</h2>");
+    let failures: Vec<&DeletionRecord> = deletions
+        .iter()
+        .filter(|record| record.outcome == DeletionOutcome::Failed)
+        .collect();
+    if failures.is_empty() {
+        let _ = writeln!(out, "<p class=\"none\">No deletions failed.</p>");
+    } else {
+        let _ = writeln!(
+            out,
+            "<table>\n<tr><th>File</th><th>Reason</th><th>Attempted</th></tr>"
+        );
+        for record in failures {
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"path\">{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&record.file_path.display().to_string()),
+                escape(record.error_message.as_deref().unwrap_or("unknown")),
+                timestamp(record.attempted_at),
+            );
+        }
+        let _ = writeln!(out, "</table>");
+    }
+
+    let _ = writeln!(out, "<h2>Directories that could not be searched</h2>");
+    if errors.is_empty() {
+        let _ = writeln!(out, "<p class=\"none\">Every directory was searched.</p>");
+    } else {
+        let _ = writeln!(
+            out,
+            "<table>\n<tr><th>Directory</th><th>Error</th><th>Last tried</th></tr>"
+        );
+        for state in errors {
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"path\">{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&state.path.display().to_string()),
+                escape(state.error_message.as_deref().unwrap_or_default()),
+                timestamp(state.last_searched_at),
+            );
+        }
+        let _ = writeln!(out, "</table>");
+    }
+
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+fn tree_item(out: &mut String, tree: &HitTree, depth: usize) {
+    let name = if depth == 0 {
+        tree.path.display().to_string()
+    } else {
+        tree.path.file_name().map_or_else(
+            || tree.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    };
+    let label = format!(
+        "<span class=\"path\">{}</span><span class=\"count\">{}</span>",
+        escape(&name),
+        tree.hits
+    );
+
+    if tree.children.is_empty() {
+        let _ = writeln!(out, "<li>{label}</li>");
+        return;
+    }
+
+    let _ = writeln!(
+        out,
+        "<li><details{}><summary>{label}</summary><ul>",
+        if depth < OPEN_DEPTH { " open" } else { "" }
+    );
+    for child in &tree.children {
+        tree_item(out, child, depth + 1);
+    }
+    let _ = writeln!(out, "</ul></details></li>");
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
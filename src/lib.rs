@@ -6,7 +6,7 @@
     clippy::perf
 )]
 
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use regex::RegexSet;
@@ -18,17 +18,46 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc, Mutex,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::fs as async_fs;
 use tokio_util::sync::CancellationToken;
+use tracing::{debug, info_span, trace, warn, Instrument};
 
-use crate::cache::{Cache, DirectoryState, DirectoryStatus};
-use color_eyre::eyre::Result;
-
+use crate::appledouble::AppleDoubleMode;
+use crate::archives::{ArchiveHit, ArchiveKind, ArchiveMode};
+use crate::backend::CacheBackend;
+use crate::cache::{
+    DeletionOutcome, DeletionRecord, DirectoryStamp, DirectoryState, DirectoryStatus, Hit,
+};
+use crate::filters::HitFilter;
+use crate::ownership::OwnerFilter;
+use crate::profiles::ProfileSet;
+use crate::volume::{MetadataDir, VolumeCleanup};
+use color_eyre::eyre::{eyre, Result};
+
+pub mod appledouble;
+pub mod archives;
+pub mod backend;
 pub mod cache;
 pub mod cli;
 pub mod config;
+pub mod filters;
+pub mod html;
+pub mod logging;
+pub mod metrics;
+pub mod migrations;
+pub mod ownership;
+pub mod portable;
+pub mod profiles;
+pub mod report;
+pub mod review;
+pub mod roots;
+pub mod safety;
+pub mod volume;
+
+/// Every progress bar and spinner is drawn through this, so log output can hide them while it prints
+pub(crate) static PROGRESS: Lazy<MultiProgress> = Lazy::new(MultiProgress::new);
 
 // Pre-compiled regex set for system path filtering
 static SYSTEM_PATH_PATTERNS: Lazy<RegexSet> = Lazy::new(|| {
@@ -104,11 +133,14 @@ impl Verbosity {
 
 #[derive(Debug, Default)]
 struct SearchStats {
-    new_searches: AtomicUsize,     // Directories searched for the first time
-    resumed_searches: AtomicUsize, // Directories resumed from incomplete searches
-    skipped_cached: AtomicUsize,   // Directories skipped because already cached
-    found: AtomicUsize,            // Total .DS_Store files found
-    errors: AtomicUsize,           // Directories with errors
+    new_searches: AtomicUsize,      // Directories searched for the first time
+    resumed_searches: AtomicUsize,  // Directories resumed from incomplete searches
+    skipped_cached: AtomicUsize,    // Directories skipped because already cached
+    found: AtomicUsize,             // Total .DS_Store files found
+    errors: AtomicUsize,            // Directories with errors
+    skipped_ownership: AtomicUsize, // Hits left alone because of their owner or group
+    skipped_filtered: AtomicUsize,  // Files that failed the age or size limits
+    skipped_paired: AtomicUsize,    // AppleDouble files whose data file still exists
 }
 
 impl SearchStats {
@@ -156,31 +188,142 @@ impl SearchStats {
         self.errors.load(Ordering::Relaxed)
     }
 
+    fn get_skipped_ownership(&self) -> usize {
+        self.skipped_ownership.load(Ordering::Relaxed)
+    }
+
+    fn increment_skipped_filtered(&self) {
+        self.skipped_filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_skipped_filtered(&self) -> usize {
+        self.skipped_filtered.load(Ordering::Relaxed)
+    }
+
+    fn increment_skipped_paired(&self) {
+        self.skipped_paired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get_skipped_paired(&self) -> usize {
+        self.skipped_paired.load(Ordering::Relaxed)
+    }
+
     fn get_total_searched(&self) -> usize {
         self.get_new() + self.get_resumed()
     }
 }
 
-async fn find_ds_stores_progressive(
-    root: &Path,
+/// What a single `dds` run did, for summaries, reports and metrics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    /// The search directory, or the directory holding all of them
+    pub root: PathBuf,
+    /// Every search directory of the run, after nested ones were dropped
+    pub roots: Vec<PathBuf>,
+    /// The search session this run started or resumed
+    pub session_id: String,
+    pub dry_run: bool,
+    /// Directories searched for the first time
+    pub directories_searched: usize,
+    /// Directories picked up again from an interrupted search
+    pub directories_resumed: usize,
+    /// Directories skipped because the cache had them as fresh
+    pub directories_skipped: usize,
+    pub directories_errored: usize,
+    /// Hits left alone because their owner or group did not match the filter
+    pub skipped_ownership: usize,
+    /// `.DS_Store` files that did not meet the age or size limits
+    pub skipped_filtered: usize,
+    /// AppleDouble `._` files kept because their data file still exists
+    pub skipped_paired: usize,
+    pub hits_found: usize,
+    pub deleted: usize,
+    /// Hits that had already disappeared by the time they were deleted
+    pub missing: usize,
+    pub failed: usize,
+    /// Hits kept because they were rejected in a review, during this run or an earlier one
+    pub rejected: usize,
+    /// Hits kept because they are under a protected path
+    pub protected: usize,
+    /// Every hit with the size it had when it was found
+    pub hits: Vec<Hit>,
+    /// Combined size of every hit whose size could be read
+    pub bytes_found: u64,
+    /// Combined size of the hits that were actually deleted
+    pub bytes_reclaimed: u64,
+    /// Archives holding `__MACOSX/`, `.DS_Store` or `._` entries
+    pub archives: Vec<ArchiveHit>,
+    /// Archives rewritten without those entries
+    pub archives_cleaned: usize,
+    pub archives_failed: usize,
+    /// macOS metadata directories at the root of the search directory
+    pub volume_dirs: Vec<MetadataDir>,
+    pub volume_dirs_removed: usize,
+    pub volume_dirs_failed: usize,
+    pub duration: Duration,
+}
+
+impl RunSummary {
+    fn from_stats(
+        roots: &[PathBuf],
+        session_id: &str,
+        dry_run: bool,
+        stats: &SearchStats,
+        hits: &[Hit],
+    ) -> Self {
+        Self {
+            root: roots::common_ancestor(roots),
+            roots: roots.to_vec(),
+            session_id: session_id.to_string(),
+            dry_run,
+            directories_searched: stats.get_new(),
+            directories_resumed: stats.get_resumed(),
+            directories_skipped: stats.get_skipped(),
+            directories_errored: stats.get_errors(),
+            skipped_ownership: stats.get_skipped_ownership(),
+            skipped_filtered: stats.get_skipped_filtered(),
+            skipped_paired: stats.get_skipped_paired(),
+            hits_found: hits.len(),
+            hits: hits.to_vec(),
+            bytes_found: hits.iter().filter_map(|hit| hit.size).sum(),
+            ..Default::default()
+        }
+    }
+}
+
+async fn find_ds_stores_progressive<C: CacheBackend + ?Sized>(
+    roots: &[PathBuf],
     recursive: bool,
-    cache: &mut Cache,
+    cache: &mut C,
     verbosity: Verbosity,
     dry_run: bool,
+    options: &RunOptions,
     cancellation_token: CancellationToken,
-) -> Result<(Vec<PathBuf>, SearchStats)> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_message("Finding .DS_Store files...");
+) -> Result<(Vec<Hit>, Vec<ArchiveHit>, SearchStats, String)> {
+    let spinner = PROGRESS.add(ProgressBar::new_spinner());
+    let files = options.profiles.files();
+    spinner.set_message(format!("Finding {files}..."));
     spinner.enable_steady_tick(Duration::from_millis(100));
 
     let stats = Arc::new(SearchStats::new());
-    let found_files = Arc::new(Mutex::new(Vec::new()));
+    let settings = WalkSettings {
+        recursive,
+        filter: options.filter,
+        appledouble: options.appledouble,
+        archives: options.archives,
+        profiles: Arc::new(options.profiles.clone()),
+    };
+    let found = Arc::new(Found::default());
     let completed_dirs = Arc::new(Mutex::new(Vec::new()));
     let processing_dirs = Arc::new(Mutex::new(HashSet::new()));
     let subdirs_queue = Arc::new(Mutex::new(Vec::new()));
 
     // Try to resume an existing session or start a new one
-    let (session_id, is_resumed) = match cache.resume_session(root, recursive, dry_run).await? {
+    let profiles = options.profiles.key();
+    let (session_id, is_resumed) = match cache
+        .resume_session(roots, recursive, dry_run, &profiles)
+        .await?
+    {
         Some(resumed_session_id) => {
             let work_count = cache.get_work_count(&resumed_session_id).await?;
             if verbosity.is_not_quiet() {
@@ -192,10 +335,12 @@ async fn find_ds_stores_progressive(
         }
         None => {
             // Start new session
-            let new_session_id = cache.start_session(root, recursive, dry_run).await?;
+            let new_session_id = cache
+                .start_session(roots, recursive, dry_run, &profiles)
+                .await?;
 
-            // Enqueue the root directory to start the search
-            cache.enqueue_work(&new_session_id, root, 0).await?;
+            // Enqueue the root directories to start the search
+            cache.enqueue_work_batch(&new_session_id, roots, 0).await?;
 
             if verbosity.is_not_quiet() {
                 eprintln!("Starting new search session: {new_session_id}");
@@ -203,14 +348,16 @@ async fn find_ds_stores_progressive(
             (new_session_id, false)
         }
     };
+    tracing::Span::current().record("session_id", tracing::field::display(&session_id));
 
     // Load previously found files if resuming
     if is_resumed {
-        let previously_found = cache.load_found_files(&session_id).await?;
+        let previously_found = cache.load_hits(&session_id).await?;
         let prev_count = previously_found.len();
-        found_files
+        found
+            .hits
             .lock()
-            .expect("Failed to acquire lock on found_files")
+            .expect("Failed to acquire lock on found hits")
             .extend(previously_found);
         if prev_count > 0 {
             // Update stats to reflect previously found files
@@ -218,7 +365,7 @@ async fn find_ds_stores_progressive(
                 stats.increment_found();
             }
             if verbosity.is_not_quiet() {
-                eprintln!("Loaded {prev_count} previously found .DS_Store files");
+                eprintln!("Loaded {prev_count} previously found {files}");
             }
         }
 
@@ -284,17 +431,16 @@ async fn find_ds_stores_progressive(
             }
 
             // Save found files before marking session status
-            let current_found: Vec<PathBuf> = {
-                let files = found_files
+            let current_found: Vec<Hit> = {
+                let files = found
+                    .hits
                     .lock()
-                    .expect("Failed to acquire lock on found_files");
+                    .expect("Failed to acquire lock on found hits");
                 files.clone()
             };
             if !current_found.is_empty() {
-                cache.save_found_files(&session_id, &current_found).await?;
-                if verbosity.is_verbose() {
-                    eprintln!("Saved {} found files to session", current_found.len());
-                }
+                cache.save_hits(&session_id, &current_found).await?;
+                debug!(count = current_found.len(), "Saved found files to session");
             }
 
             // Check if work is actually complete
@@ -308,17 +454,7 @@ async fn find_ds_stores_progressive(
             }
 
             // Return the progress made so far
-            let found = Arc::try_unwrap(found_files)
-                .map(|mutex| {
-                    mutex
-                        .into_inner()
-                        .expect("Failed to unwrap mutex into inner value")
-                })
-                .unwrap_or_else(|arc| {
-                    arc.lock()
-                        .expect("Failed to acquire lock on found_files Arc")
-                        .clone()
-                });
+            let (hits, archives) = found.take();
 
             let final_stats = Arc::try_unwrap(stats).unwrap_or_else(|arc| SearchStats {
                 new_searches: AtomicUsize::new(arc.get_new()),
@@ -326,9 +462,12 @@ async fn find_ds_stores_progressive(
                 skipped_cached: AtomicUsize::new(arc.get_skipped()),
                 found: AtomicUsize::new(arc.get_found()),
                 errors: AtomicUsize::new(arc.get_errors()),
+                skipped_ownership: AtomicUsize::new(arc.get_skipped_ownership()),
+                skipped_filtered: AtomicUsize::new(arc.get_skipped_filtered()),
+                skipped_paired: AtomicUsize::new(arc.get_skipped_paired()),
             });
 
-            return Ok((found, final_stats));
+            return Ok((hits, archives, final_stats, session_id));
         }
 
         // Get work from persistent queue (peek without removing)
@@ -337,25 +476,21 @@ async fn find_ds_stores_progressive(
 
         if work_items_empty && tasks.is_empty() {
             // No more work and no running tasks
-            if verbosity.is_verbose() {
-                eprintln!(
-                    "Breaking from main loop: work_items_empty={work_items_empty}, tasks.len()={}",
-                    tasks.len()
-                );
-            }
+            trace!("Work queue drained and no tasks running");
             break;
         }
 
-        if verbosity.is_verbose() && work_items_empty {
-            eprintln!(
-                "Main loop continues: work_items_empty={work_items_empty}, tasks.len()={}",
-                tasks.len()
+        if work_items_empty {
+            trace!(
+                tasks = tasks.len(),
+                "Work queue empty, waiting on running tasks"
             );
         }
 
         // Filter work items based on cache status and collect IDs to remove
         let mut items_to_process = Vec::new();
         let mut all_item_ids = Vec::new();
+        let mut unchanged_children = Vec::new();
 
         for work_item in potential_work_items {
             // Collect ID for removal
@@ -367,39 +502,26 @@ async fn find_ds_stores_progressive(
             let dir_status = cache.get_directory_status(&work_item.path).await?;
 
             match dir_status {
-                DirectoryStatus::Fresh => {
+                // The cache only remembers `.DS_Store` files, so looking for anything
+                // else means reading every directory again
+                DirectoryStatus::Fresh if !settings.reads_every_directory() => {
                     stats.increment_skipped();
-                    if verbosity.is_verbose() {
-                        eprintln!("Skipping cached directory: {}", work_item.path.display());
+                    debug!(dir = %work_item.path.display(), "Skipping cached directory");
+                    // The directory itself is unchanged, but its children may not be
+                    if recursive {
+                        unchanged_children
+                            .extend(cache.get_cached_children(&work_item.path).await?);
                     }
                 }
                 DirectoryStatus::Incomplete => {
                     stats.increment_resumed();
+                    debug!(dir = %work_item.path.display(), "Resuming incomplete directory");
                     items_to_process.push(work_item);
-                    if verbosity.is_verbose() {
-                        eprintln!(
-                            "Resuming incomplete directory: {}",
-                            items_to_process
-                                .last()
-                                .expect("items_to_process should not be empty")
-                                .path
-                                .display()
-                        );
-                    }
                 }
-                DirectoryStatus::NotCached | DirectoryStatus::Stale => {
+                DirectoryStatus::Fresh | DirectoryStatus::NotCached | DirectoryStatus::Stale => {
                     stats.increment_new();
+                    debug!(dir = %work_item.path.display(), status = ?dir_status, "Searching directory");
                     items_to_process.push(work_item);
-                    if verbosity.is_verbose() {
-                        eprintln!(
-                            "New directory: {}",
-                            items_to_process
-                                .last()
-                                .expect("items_to_process should not be empty")
-                                .path
-                                .display()
-                        );
-                    }
                 }
             }
         }
@@ -409,6 +531,12 @@ async fn find_ds_stores_progressive(
             cache.remove_work_items(&all_item_ids).await?;
         }
 
+        if !unchanged_children.is_empty() {
+            cache
+                .enqueue_work_batch(&session_id, &unchanged_children, 0)
+                .await?;
+        }
+
         // Process the items that need processing
         for work_item in items_to_process {
             // Wrap the path in Arc to avoid multiple clones
@@ -436,53 +564,54 @@ async fn find_ds_stores_progressive(
                 tasks = remaining;
 
                 if let Err(e) = result {
-                    eprintln!("Task error: {e}");
+                    warn!(error = %e, "Directory task failed");
                 }
             }
 
             let stats_clone = Arc::clone(&stats);
-            let found_files_clone = Arc::clone(&found_files);
+            let found_clone = Arc::clone(&found);
             let completed_dirs_clone = Arc::clone(&completed_dirs);
             let processing_dirs_clone = Arc::clone(&processing_dirs);
             let subdirs_queue_clone = Arc::clone(&subdirs_queue);
             let session_id_clone = session_id.clone();
             let path_clone = Arc::clone(&work_path);
-            let recursive_clone = recursive;
-
-            let task = tokio::spawn(async move {
-                // Add timeout to prevent hanging on problematic directories
-                let result = tokio::time::timeout(
-                    Duration::from_secs(30), // 30 second timeout per directory
-                    process_directory_with_persistent_queue(
-                        (*path_clone).clone(),
-                        &session_id_clone,
-                        &stats_clone,
-                        &found_files_clone,
-                        &completed_dirs_clone,
-                        &subdirs_queue_clone,
-                        recursive_clone,
-                    ),
-                )
-                .await;
-
-                // Remove from processing set when done
-                processing_dirs_clone
-                    .lock()
-                    .expect("Failed to acquire lock on processing_dirs")
-                    .remove(&*path_clone);
-
-                match result {
-                    Ok(inner_result) => inner_result,
-                    Err(_) => {
-                        eprintln!(
-                            "Warning: Timeout processing directory: {}",
-                            path_clone.display()
-                        );
-                        stats_clone.increment_errors();
-                        Ok(())
+            let settings_clone = settings.clone();
+
+            let dir_span = info_span!("directory", path = %work_path.display());
+            let task = tokio::spawn(
+                async move {
+                    // Add timeout to prevent hanging on problematic directories
+                    let result = tokio::time::timeout(
+                        Duration::from_secs(30), // 30 second timeout per directory
+                        process_directory_with_persistent_queue(
+                            (*path_clone).clone(),
+                            &session_id_clone,
+                            &stats_clone,
+                            &found_clone,
+                            &completed_dirs_clone,
+                            &subdirs_queue_clone,
+                            settings_clone,
+                        ),
+                    )
+                    .await;
+
+                    // Remove from processing set when done
+                    processing_dirs_clone
+                        .lock()
+                        .expect("Failed to acquire lock on processing_dirs")
+                        .remove(&*path_clone);
+
+                    match result {
+                        Ok(inner_result) => inner_result,
+                        Err(_) => {
+                            warn!("Timed out processing directory");
+                            stats_clone.increment_errors();
+                            Ok(())
+                        }
                     }
                 }
-            });
+                .instrument(dir_span),
+            );
 
             tasks.push(task);
             total_processed += 1;
@@ -505,7 +634,7 @@ async fn find_ds_stores_progressive(
         let total_searched = stats.get_new() + stats.get_resumed();
         let message = if stats.get_resumed() > 0 {
             format!(
-                "Searching: {new} new + {resumed} resumed = {total_searched} total | Found: {found} .DS_Store files | Skipped: {skipped} cached | Queue: {work_remaining} remaining",
+                "Searching: {new} new + {resumed} resumed = {total_searched} total | Found: {found} {files} | Skipped: {skipped} cached | Queue: {work_remaining} remaining",
                 new = stats.get_new(),
                 resumed = stats.get_resumed(),
                 found = stats.get_found(),
@@ -513,7 +642,7 @@ async fn find_ds_stores_progressive(
             )
         } else {
             format!(
-                "Searching: {total_searched} directories | Found: {found} .DS_Store files | Skipped: {skipped} cached | Queue: {work_remaining} remaining",
+                "Searching: {total_searched} directories | Found: {found} {files} | Skipped: {skipped} cached | Queue: {work_remaining} remaining",
                 found = stats.get_found(),
                 skipped = stats.get_skipped()
             )
@@ -554,26 +683,26 @@ async fn find_ds_stores_progressive(
                 } else {
                     cache.mark_completed_batch(&dirs_to_update).await?;
                 }
-                if verbosity.is_verbose() {
-                    eprintln!("Cache flush: {count} directories marked as completed");
-                }
+                debug!(count, "Cache flush: directories marked as completed");
             }
 
             // Force a cache flush to disk
             cache.flush_pending().await?;
 
+            // Let other dds processes know this session is still being worked on
+            cache.heartbeat().await?;
+
             // Save found files periodically
-            let current_found: Vec<PathBuf> = {
-                let files = found_files
+            let current_found: Vec<Hit> = {
+                let files = found
+                    .hits
                     .lock()
-                    .expect("Failed to acquire lock on found_files");
+                    .expect("Failed to acquire lock on found hits");
                 files.clone()
             };
             if !current_found.is_empty() {
-                cache.save_found_files(&session_id, &current_found).await?;
-                if verbosity.is_verbose() {
-                    eprintln!("Saved {} found files to session", current_found.len());
-                }
+                cache.save_hits(&session_id, &current_found).await?;
+                debug!(count = current_found.len(), "Saved found files to session");
             }
         }
 
@@ -590,9 +719,7 @@ async fn find_ds_stores_progressive(
                 cache
                     .enqueue_work_batch(&subdir_session_id, &subdirs, 0)
                     .await?;
-                if verbosity.is_verbose() {
-                    eprintln!("Enqueued {} subdirectories for processing", subdirs.len());
-                }
+                trace!(count = subdirs.len(), "Enqueued subdirectories");
             }
         }
 
@@ -609,32 +736,15 @@ async fn find_ds_stores_progressive(
                     .lock()
                     .expect("Failed to acquire lock on processing_dirs")
                     .iter()
+                    .take(10)
                     .cloned()
                     .collect();
-                eprintln!(
-                    "\n[DEBUG] Stuck in empty queue loop for {} seconds:",
-                    empty_queue_loop_count / 10
+                debug!(
+                    waited_secs = empty_queue_loop_count / 10,
+                    tasks = tasks.len(),
+                    processing = ?processing_snapshot,
+                    "Waiting on directory tasks with an empty work queue"
                 );
-                eprintln!("  - work_items_empty: {work_items_empty}");
-                eprintln!("  - tasks.len(): {}", tasks.len());
-                eprintln!("  - processing_dirs.len(): {}", processing_snapshot.len());
-
-                if !processing_snapshot.is_empty() {
-                    eprintln!("  - Still processing directories:");
-                    for (i, path) in processing_snapshot.iter().enumerate() {
-                        if i < 10 {
-                            eprintln!("    {}: {}", i + 1, path.display());
-                        }
-                    }
-                    if processing_snapshot.len() > 10 {
-                        eprintln!("    ... and {} more", processing_snapshot.len() - 10);
-                    }
-                } else {
-                    eprintln!(
-                        "  - No directories in processing set (but {} tasks remain???)",
-                        tasks.len()
-                    );
-                }
             }
             tokio::time::sleep(Duration::from_millis(100)).await;
         } else {
@@ -644,13 +754,11 @@ async fn find_ds_stores_progressive(
     }
 
     // Wait for all remaining tasks
-    if verbosity.is_verbose() && !tasks.is_empty() {
-        eprintln!("Waiting for {} remaining tasks to complete...", tasks.len());
-    }
+    debug!(
+        tasks = tasks.len(),
+        "Waiting for remaining tasks to complete"
+    );
     futures::future::join_all(tasks).await;
-    if verbosity.is_verbose() {
-        eprintln!("All tasks completed.");
-    }
 
     // Final batch update
     let remaining_dirs: Vec<DirectoryState> = {
@@ -689,27 +797,22 @@ async fn find_ds_stores_progressive(
         }
     }
 
-    // Complete the session
-    cache.complete_session().await?;
+    // Keep the full hit list with the session so history reports can see it
+    {
+        let current_found: Vec<Hit> = found
+            .hits
+            .lock()
+            .expect("Failed to acquire lock on found hits")
+            .clone();
+        cache.save_hits(&session_id, &current_found).await?;
+    }
 
+    // The session stays open until the run is over; `bye_bye_ds_stores_in` closes it
     spinner.finish_and_clear();
 
-    if verbosity.is_verbose() {
-        eprintln!("Search session completed. Processed {total_processed} directories total.");
-    }
+    debug!(total_processed, "Search session completed");
 
-    let found = Arc::try_unwrap(found_files)
-        .map(|mutex| {
-            mutex
-                .into_inner()
-                .expect("Failed to unwrap mutex into inner value")
-        })
-        .unwrap_or_else(|arc| {
-            let mut locked = arc
-                .lock()
-                .expect("Failed to acquire lock on found_files Arc");
-            std::mem::take(&mut *locked)
-        });
+    let (hits, archives) = found.take();
 
     let final_stats = Arc::try_unwrap(stats).unwrap_or_else(|arc| SearchStats {
         new_searches: AtomicUsize::new(arc.get_new()),
@@ -717,20 +820,67 @@ async fn find_ds_stores_progressive(
         skipped_cached: AtomicUsize::new(arc.get_skipped()),
         found: AtomicUsize::new(arc.get_found()),
         errors: AtomicUsize::new(arc.get_errors()),
+        skipped_ownership: AtomicUsize::new(arc.get_skipped_ownership()),
+        skipped_filtered: AtomicUsize::new(arc.get_skipped_filtered()),
+        skipped_paired: AtomicUsize::new(arc.get_skipped_paired()),
     });
 
-    Ok((found, final_stats))
+    Ok((hits, archives, final_stats, session_id))
 }
 
 type SubDirQueue = Arc<Mutex<Vec<(String, Vec<PathBuf>)>>>;
+
+/// Everything the directory tasks turn up for the run to act on
+#[derive(Debug, Default)]
+struct Found {
+    hits: Mutex<Vec<Hit>>,
+    archives: Mutex<Vec<ArchiveHit>>,
+}
+
+impl Found {
+    /// Take everything found so far, leaving the lists empty
+    fn take(&self) -> (Vec<Hit>, Vec<ArchiveHit>) {
+        let hits = std::mem::take(
+            &mut *self
+                .hits
+                .lock()
+                .expect("Failed to acquire lock on found hits"),
+        );
+        let archives = std::mem::take(
+            &mut *self
+                .archives
+                .lock()
+                .expect("Failed to acquire lock on found archives"),
+        );
+        (hits, archives)
+    }
+}
+
+/// How each directory task walks its directory
+#[derive(Debug, Clone)]
+struct WalkSettings {
+    recursive: bool,
+    filter: HitFilter,
+    appledouble: AppleDoubleMode,
+    archives: ArchiveMode,
+    profiles: Arc<ProfileSet>,
+}
+
+impl WalkSettings {
+    /// Whether directories the cache has as fresh still have to be read
+    fn reads_every_directory(&self) -> bool {
+        self.appledouble.is_enabled() || self.archives.is_enabled() || !self.profiles.is_default()
+    }
+}
+
 async fn process_directory_with_persistent_queue(
     dir: PathBuf,
     session_id: &str,
     stats: &Arc<SearchStats>,
-    found_files: &Arc<Mutex<Vec<PathBuf>>>,
+    found: &Arc<Found>,
     completed_dirs: &Arc<Mutex<Vec<DirectoryState>>>,
     subdirs_queue: &SubDirQueue,
-    recursive: bool,
+    settings: WalkSettings,
 ) -> Result<()> {
     let now = chrono::Utc::now().timestamp();
     let mut ds_store_found = false;
@@ -751,6 +901,8 @@ async fn process_directory_with_persistent_queue(
             ds_store_found: false,
             ds_store_deleted: false,
             error_message,
+            mtime_ns: None,
+            inode: None,
         };
         completed_dirs
             .lock()
@@ -760,7 +912,7 @@ async fn process_directory_with_persistent_queue(
     }
 
     // Check if directory is accessible (permissions)
-    match async_fs::metadata(&dir).await {
+    let stamp = match async_fs::metadata(&dir).await {
         Ok(metadata) => {
             // Check if it's actually a directory
             if !metadata.is_dir() {
@@ -773,6 +925,8 @@ async fn process_directory_with_persistent_queue(
                     ds_store_found: false,
                     ds_store_deleted: false,
                     error_message,
+                    mtime_ns: None,
+                    inode: None,
                 };
                 completed_dirs
                     .lock()
@@ -780,6 +934,9 @@ async fn process_directory_with_persistent_queue(
                     .push(dir_state);
                 return Ok(());
             }
+
+            // Record the stamp before reading so later changes invalidate this search
+            DirectoryStamp::from_metadata(&metadata)
         }
         Err(e) => {
             // Permission denied or other access error
@@ -793,6 +950,8 @@ async fn process_directory_with_persistent_queue(
                 ds_store_found: false,
                 ds_store_deleted: false,
                 error_message,
+                mtime_ns: None,
+                inode: None,
             };
             completed_dirs
                 .lock()
@@ -800,7 +959,7 @@ async fn process_directory_with_persistent_queue(
                 .push(dir_state);
             return Ok(());
         }
-    }
+    };
 
     // Check if it's a symlink to avoid loops
     match async_fs::symlink_metadata(&dir).await {
@@ -815,6 +974,8 @@ async fn process_directory_with_persistent_queue(
                 ds_store_found: false,
                 ds_store_deleted: false,
                 error_message,
+                mtime_ns: None,
+                inode: None,
             };
             completed_dirs
                 .lock()
@@ -835,7 +996,7 @@ async fn process_directory_with_persistent_queue(
                     continue;
                 };
 
-                if file_type.is_dir() && recursive {
+                if file_type.is_dir() && settings.recursive {
                     // Skip symlinks when queueing subdirectories
                     if let Ok(metadata) = async_fs::symlink_metadata(&path).await {
                         if !metadata.file_type().is_symlink() {
@@ -846,15 +1007,52 @@ async fn process_directory_with_persistent_queue(
                         }
                     }
                 } else if file_type.is_file() {
-                    // Check if it's a .DS_Store file
-                    if let Some(name) = path.file_name() {
-                        if name == ".DS_Store" {
-                            found_files
+                    let Some(name) = path.file_name() else {
+                        continue;
+                    };
+                    if name == ".DS_Store" {
+                        // Recorded on the directory either way, so later runs with other
+                        // limits or profiles still know the file is there
+                        ds_store_found = true;
+                    }
+                    let selected = name
+                        .to_str()
+                        .is_some_and(|name| settings.profiles.matches(name));
+                    // A `._` file gets the AppleDouble checks whether a profile or
+                    // `--appledouble` picked it
+                    let data_file = appledouble::data_file(&path)
+                        .filter(|_| selected || settings.appledouble.is_enabled());
+                    if let Some(data_file) = data_file {
+                        if !appledouble::has_magic(&path).await {
+                            continue;
+                        }
+                        let paired = async_fs::symlink_metadata(&data_file).await.is_ok();
+                        if paired && settings.appledouble != AppleDoubleMode::All {
+                            stats.increment_skipped_paired();
+                            continue;
+                        }
+                        let metadata = entry.metadata().await.ok();
+                        record_hit(path, metadata, &settings, found, stats);
+                    } else if selected {
+                        let metadata = entry.metadata().await.ok();
+                        record_hit(path, metadata, &settings, found, stats);
+                    } else if settings.archives.is_enabled()
+                        && ArchiveKind::from_path(&path).is_some()
+                    {
+                        // Reading an archive's index is blocking work
+                        let archive_path = path.clone();
+                        match tokio::task::spawn_blocking(move || archives::scan(&archive_path))
+                            .await?
+                        {
+                            Ok(Some(archive)) => found
+                                .archives
                                 .lock()
-                                .expect("Failed to acquire lock on found_files")
-                                .push(path);
-                            stats.increment_found();
-                            ds_store_found = true;
+                                .expect("Failed to acquire lock on found archives")
+                                .push(archive),
+                            Ok(None) => {}
+                            Err(err) => {
+                                debug!(archive = %path.display(), error = %err, "Could not read archive");
+                            }
                         }
                     }
                 }
@@ -884,6 +1082,8 @@ async fn process_directory_with_persistent_queue(
         ds_store_found,
         ds_store_deleted: false, // Will be updated later when files are deleted
         error_message,
+        mtime_ns: stamp.mtime_ns,
+        inode: stamp.inode,
     };
 
     completed_dirs
@@ -894,20 +1094,292 @@ async fn process_directory_with_persistent_queue(
     Ok(())
 }
 
-pub async fn bye_bye_ds_stores(
+/// Keep a file the walker found if it meets the age and size limits
+fn record_hit(
+    path: PathBuf,
+    metadata: Option<fs::Metadata>,
+    settings: &WalkSettings,
+    found: &Found,
+    stats: &SearchStats,
+) {
+    let passes = match &metadata {
+        Some(metadata) => settings.filter.matches(metadata, SystemTime::now()),
+        None => settings.filter.is_empty(),
+    };
+    if passes {
+        found
+            .hits
+            .lock()
+            .expect("Failed to acquire lock on found hits")
+            .push(Hit {
+                path,
+                size: metadata.map(|metadata| metadata.len()),
+            });
+        stats.increment_found();
+    } else {
+        stats.increment_skipped_filtered();
+    }
+}
+
+/// Rewrite the archives a run found without their macOS entries, returning an audit
+/// record for each attempt
+///
+/// Protected paths and the owner filter apply as they do to plain hits. One archive
+/// failing to rewrite does not stop the others.
+fn clean_archives(
+    summary: &mut RunSummary,
+    options: &RunOptions,
+    verbosity: Verbosity,
+) -> Vec<DeletionRecord> {
+    let mut audit_log = Vec::new();
+    for archive in &summary.archives {
+        if safety::is_protected(&archive.path, &options.protected_paths)
+            || !options.owners.allows(&archive.path)
+        {
+            continue;
+        }
+        // Hash and size are of the archive as it was, like those of a deleted file
+        let mut record = DeletionRecord::capture(&archive.path, Some(&summary.session_id));
+        match archives::clean(archive, options.archive_backup) {
+            Ok(cleaned) => {
+                record.outcome = DeletionOutcome::Rewritten;
+                summary.archives_cleaned += 1;
+                debug!(
+                    archive = %archive.path.display(),
+                    removed = cleaned.removed,
+                    bytes_saved = cleaned.bytes_before.saturating_sub(cleaned.bytes_after),
+                    "Rewrote archive"
+                );
+            }
+            Err(err) => {
+                record.outcome = DeletionOutcome::Failed;
+                record.error_message = Some(format!("Could not rewrite archive: {err}"));
+                summary.archives_failed += 1;
+                warn!(archive = %archive.path.display(), error = %err, "Could not rewrite archive");
+            }
+        }
+        record.attempted_at = chrono::Utc::now().timestamp();
+        audit_log.push(record);
+    }
+
+    if verbosity.is_not_quiet() && !summary.archives.is_empty() {
+        eprintln!(
+            "Rewrote {} archives without their macOS entries",
+            summary.archives_cleaned
+        );
+        if summary.archives_failed > 0 {
+            eprintln!(
+                "  {} archives could not be rewritten",
+                summary.archives_failed
+            );
+        }
+    }
+    audit_log
+}
+
+/// The metadata directories found at the volume root that a run may remove
+///
+/// A directory is left alone if it is protected, holds a protected path, or is not
+/// allowed by the owner filter.
+fn removable_volume_dirs(summary: &RunSummary, options: &RunOptions) -> Vec<MetadataDir> {
+    summary
+        .volume_dirs
+        .iter()
+        .filter(|dir| {
+            !safety::holds_protected(&dir.path, &options.protected_paths)
+                && options.owners.allows(&dir.path)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Remove the metadata directories found at the volume root, once confirmed
+///
+/// Only [`removable_volume_dirs`] are offered. Each removal is audited like a file
+/// deletion, with the size being everything the directory held.
+fn remove_volume_dirs(
+    summary: &mut RunSummary,
+    options: &RunOptions,
+    verbosity: Verbosity,
+) -> Result<Vec<DeletionRecord>> {
+    let dirs = removable_volume_dirs(summary, options);
+    if dirs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if options.volume_cleanup == VolumeCleanup::Ask
+        && !volume::confirm(
+            &summary.root,
+            &dirs,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stderr(),
+        )?
+    {
+        if verbosity.is_not_quiet() {
+            eprintln!("Leaving the macOS metadata directories in place");
+        }
+        return Ok(Vec::new());
+    }
+
+    let mut audit_log = Vec::with_capacity(dirs.len());
+    let mut bytes_removed = 0;
+    for dir in &dirs {
+        let mut record = DeletionRecord::capture(&dir.path, Some(&summary.session_id));
+        record.size = i64::try_from(dir.bytes).ok();
+        let result = fs::remove_dir_all(&dir.path);
+        if let Err(err) = &result {
+            warn!(dir = %dir.path.display(), error = %err, "Could not remove directory");
+        }
+        let record = record.with_result(&result);
+        match record.outcome {
+            DeletionOutcome::Deleted => {
+                summary.volume_dirs_removed += 1;
+                bytes_removed += dir.bytes;
+            }
+            DeletionOutcome::Failed => summary.volume_dirs_failed += 1,
+            DeletionOutcome::Missing | DeletionOutcome::Rewritten => {}
+        }
+        audit_log.push(record);
+    }
+
+    if verbosity.is_not_quiet() {
+        eprintln!(
+            "Removed {} macOS metadata directories ({})",
+            summary.volume_dirs_removed,
+            report::format_bytes(bytes_removed)
+        );
+        if summary.volume_dirs_failed > 0 {
+            eprintln!(
+                "  {} directories could not be removed",
+                summary.volume_dirs_failed
+            );
+        }
+    }
+    Ok(audit_log)
+}
+
+/// Optional behaviour for [`bye_bye_ds_stores`] beyond searching and deleting
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Review the hits on the terminal and delete only the approved ones
+    pub interactive: bool,
+    /// Refuse to delete anything if more than this many files and volume metadata
+    /// directories would be deleted, before any review; rewritten archives are not counted
+    pub max_delete: Option<usize>,
+    /// Files at or below these paths are never deleted
+    pub protected_paths: Vec<PathBuf>,
+    /// Only files and volume metadata directories with a matching owner and group are deleted
+    pub owners: OwnerFilter,
+    /// Age and size limits, applied while walking
+    pub filter: HitFilter,
+    /// Which AppleDouble `._` files to remove along with `.DS_Store`
+    pub appledouble: AppleDoubleMode,
+    /// Whether to look inside archives for macOS cruft, and whether to strip it out
+    pub archives: ArchiveMode,
+    /// Keep the original of every rewritten archive as `<name>.bak`
+    pub archive_backup: bool,
+    /// Whether to remove macOS metadata directories such as `.Spotlight-V100` from the
+    /// search directory, treating it as the root of a volume
+    pub volume_cleanup: VolumeCleanup,
+    /// Which files to delete; with no profile selected, only `.DS_Store`
+    pub profiles: ProfileSet,
+}
+
+pub async fn bye_bye_ds_stores<C: CacheBackend + ?Sized>(
     search_parent: &Path,
     recursive: &bool,
     verbosity: Verbosity,
     dryrun: &bool,
-    cache: &mut Cache,
+    cache: &mut C,
+    options: &RunOptions,
     cancellation_token: CancellationToken,
-) -> Result<()> {
+) -> Result<RunSummary> {
+    bye_bye_ds_stores_in(
+        &[search_parent.to_path_buf()],
+        recursive,
+        verbosity,
+        dryrun,
+        cache,
+        options,
+        cancellation_token,
+    )
+    .await
+}
+
+/// Search several directories in one session with one work queue, as
+/// [`bye_bye_ds_stores`] does for one
+///
+/// Repeated directories, and in a recursive search directories inside another one, are
+/// searched only once.
+pub async fn bye_bye_ds_stores_in<C: CacheBackend + ?Sized>(
+    roots: &[PathBuf],
+    recursive: &bool,
+    verbosity: Verbosity,
+    dryrun: &bool,
+    cache: &mut C,
+    options: &RunOptions,
+    cancellation_token: CancellationToken,
+) -> Result<RunSummary> {
+    let result = run_session(
+        roots,
+        recursive,
+        verbosity,
+        dryrun,
+        cache,
+        options,
+        cancellation_token.clone(),
+    )
+    .await;
+
+    // A cancelled search has already closed its session as far as it got
+    if cancellation_token.is_cancelled() {
+        return result;
+    }
+    match result {
+        Ok(summary) => {
+            cache.complete_session().await?;
+            Ok(summary)
+        }
+        Err(err) => {
+            // Leave the session for the next run to resume instead of to time out
+            if let Err(e) = cache.interrupt_session().await {
+                warn!(error = %e, "Failed to mark session as interrupted");
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Search, then delete, holding the session from the search through the last deletion
+async fn run_session<C: CacheBackend + ?Sized>(
+    roots: &[PathBuf],
+    recursive: &bool,
+    verbosity: Verbosity,
+    dryrun: &bool,
+    cache: &mut C,
+    options: &RunOptions,
+    cancellation_token: CancellationToken,
+) -> Result<RunSummary> {
+    let started = Instant::now();
+    let roots = roots::dedupe(roots.to_vec(), *recursive);
+    if roots.is_empty() {
+        return Err(eyre!("No directories to search"));
+    }
+    let search_parent = roots::common_ancestor(&roots);
+    let search_parent = search_parent.as_path();
+
     // If this is a deletion run (not dry run), first check for any previously found but undeleted files
     let mut cached_undeleted_files = Vec::new();
-    if !dryrun {
-        cached_undeleted_files = cache
-            .get_undeleted_ds_store_files(search_parent, *recursive)
-            .await?;
+    if !dryrun && options.profiles.matches(".DS_Store") {
+        for root in &roots {
+            cached_undeleted_files.extend(
+                cache
+                    .get_undeleted_ds_store_files(root, *recursive)
+                    .await?
+                    .iter()
+                    .map(|path| Hit::of(path)),
+            );
+        }
         if !cached_undeleted_files.is_empty() {
             if verbosity.is_verbose() {
                 eprintln!(
@@ -915,7 +1387,7 @@ pub async fn bye_bye_ds_stores(
                     cached_undeleted_files.len()
                 );
                 for file in &cached_undeleted_files {
-                    eprintln!("  - {}", file.display());
+                    eprintln!("  - {}", file.path.display());
                 }
             } else if verbosity.is_not_quiet() {
                 eprintln!(
@@ -926,29 +1398,61 @@ pub async fn bye_bye_ds_stores(
         }
     }
 
+    // The session id is filled in once the search has started or resumed one
+    let session_span = info_span!(
+        "session",
+        root = %search_parent.display(),
+        session_id = tracing::field::Empty
+    );
+
     // Use the new progressive search function
-    let (mut hits, stats) = find_ds_stores_progressive(
-        search_parent,
+    let (mut hits, archives, stats, session_id) = find_ds_stores_progressive(
+        &roots,
         *recursive,
         cache,
         verbosity,
         *dryrun,
+        options,
         cancellation_token,
     )
+    .instrument(session_span.clone())
     .await?;
 
+    // Deleting and reviewing happen away from the walker's own heartbeats
+    let _heartbeat = cache.keep_alive();
+
     // Add any cached undeleted files to the hits (avoiding duplicates)
     if !cached_undeleted_files.is_empty() {
-        let existing_hits: std::collections::HashSet<_> = hits.iter().cloned().collect();
+        let existing_hits: HashSet<PathBuf> = hits.iter().map(|hit| hit.path.clone()).collect();
+        let now = SystemTime::now();
         for cached_file in cached_undeleted_files {
-            if !existing_hits.contains(&cached_file) {
-                hits.push(cached_file);
+            if existing_hits.contains(&cached_file.path) {
+                continue;
+            }
+            // These never went through the walk, so hold them to the same limits here
+            if !options.filter.is_empty()
+                && !fs::metadata(&cached_file.path)
+                    .is_ok_and(|metadata| options.filter.matches(&metadata, now))
+            {
+                stats.increment_skipped_filtered();
+                continue;
             }
+            hits.push(cached_file);
         }
     }
 
+    // Hits owned by someone else are reported but never touched
+    if !options.owners.is_empty() {
+        let before = hits.len();
+        hits.retain(|hit| options.owners.allows(&hit.path));
+        stats
+            .skipped_ownership
+            .fetch_add(before - hits.len(), Ordering::Relaxed);
+    }
+
     let num_hits = hits.len();
     let searched_dirs = stats.get_total_searched();
+    let files = options.profiles.files();
 
     // Show detailed search summary if not quiet
     if verbosity.is_not_quiet() {
@@ -967,83 +1471,265 @@ pub async fn bye_bye_ds_stores(
         if stats.get_errors() > 0 {
             eprintln!("  Directories with errors: {}", stats.get_errors());
         }
-        eprintln!("  Total .DS_Store files found: {num_hits}");
+        if stats.get_skipped_filtered() > 0 {
+            eprintln!(
+                "  Files skipped by age or size limits: {}",
+                stats.get_skipped_filtered()
+            );
+        }
+        if stats.get_skipped_ownership() > 0 {
+            eprintln!(
+                "  Files skipped for ownership: {}",
+                stats.get_skipped_ownership()
+            );
+        }
+        if stats.get_skipped_paired() > 0 {
+            eprintln!(
+                "  AppleDouble files kept next to their data file: {}",
+                stats.get_skipped_paired()
+            );
+        }
+        eprintln!("  Total {files} found: {num_hits}");
+        if options.appledouble.is_enabled() {
+            let appledouble_hits = hits
+                .iter()
+                .filter(|hit| appledouble::data_file(&hit.path).is_some())
+                .count();
+            eprintln!("    of which AppleDouble `._` files: {appledouble_hits}");
+        }
+        if options.archives.is_enabled() {
+            eprintln!("  Archives with macOS entries: {}", archives.len());
+            for archive in &archives {
+                eprintln!(
+                    "    {}: {} of {} entries",
+                    archive.path.display(),
+                    archive.cruft.len(),
+                    archive.entries
+                );
+            }
+        }
         eprintln!();
     }
 
+    // Whole directories are reported apart from the file-level hits
+    let volume_dirs = if options.volume_cleanup.is_enabled() {
+        roots
+            .iter()
+            .flat_map(|root| volume::find_metadata_dirs(root))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
     // if a dry run is requested, early return
     if dryrun == &true {
-        let parting_message = if *recursive {
-            format!(
-                "Dry run: {num_hits} .DS_Store files found in {} and its {searched_dirs} subdirectories.", search_parent.display()
-            )
-        } else {
-            format!(
-                "Dry run: {num_hits} .DS_Store files found in {}.",
-                search_parent.display()
-            )
-        };
-        eprintln!("{parting_message}");
-        return Ok(());
+        if !volume_dirs.is_empty() && verbosity.is_not_quiet() {
+            eprintln!(
+                "Dry run: {} macOS metadata directories would be removed from {}:",
+                volume_dirs.len(),
+                describe_roots(&roots)
+            );
+            for dir in &volume_dirs {
+                eprintln!(
+                    "  {}  ({} files, {})",
+                    dir.path.display(),
+                    dir.files,
+                    report::format_bytes(dir.bytes)
+                );
+            }
+        }
+        eprintln!(
+            "Dry run: {num_hits} {files} found in {}.",
+            describe_search(&roots, *recursive, searched_dirs)
+        );
+        return Ok(RunSummary {
+            duration: started.elapsed(),
+            archives,
+            volume_dirs,
+            ..RunSummary::from_stats(&roots, &session_id, true, &stats, &hits)
+        });
+    }
+
+    let mut summary = RunSummary {
+        archives,
+        volume_dirs,
+        ..RunSummary::from_stats(&roots, &session_id, false, &stats, &hits)
+    };
+
+    // Protected paths are never touched, whatever else is asked for
+    if !options.protected_paths.is_empty() {
+        let before = hits.len();
+        hits.retain(|hit| !safety::is_protected(&hit.path, &options.protected_paths));
+        summary.protected = before - hits.len();
+        if summary.protected > 0 && verbosity.is_not_quiet() {
+            eprintln!(
+                "Leaving {} {files} under protected paths",
+                summary.protected
+            );
+        }
+    }
+
+    // Files rejected during an earlier review stay where they are
+    let mut rejected: HashSet<PathBuf> = HashSet::new();
+    for root in &roots {
+        rejected.extend(cache.load_rejections(root).await?);
+    }
+    if !rejected.is_empty() {
+        let before = hits.len();
+        hits.retain(|hit| !rejected.contains(&hit.path));
+        summary.rejected = before - hits.len();
+        if summary.rejected > 0 && verbosity.is_not_quiet() {
+            eprintln!(
+                "Keeping {} {files} rejected in an earlier review",
+                summary.rejected
+            );
+        }
+    }
+
+    // Checked before any review, so nobody answers prompts for a run that will be refused
+    if let Some(max_delete) = options.max_delete {
+        let volume_dirs = removable_volume_dirs(&summary, options).len();
+        if hits.len() + volume_dirs > max_delete {
+            let what = match volume_dirs {
+                0 => format!("{} {files}", hits.len()),
+                _ => format!(
+                    "{} {files} and {volume_dirs} macOS metadata directories",
+                    hits.len()
+                ),
+            };
+            return Err(eyre!(
+                "{what} would be deleted, more than the limit of {max_delete}; nothing was deleted"
+            ));
+        }
+    }
+
+    if options.interactive {
+        let review = review::review(
+            search_parent,
+            hits,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stderr(),
+        )?;
+        let rejected_now: Vec<PathBuf> = review.rejected.into_iter().map(|hit| hit.path).collect();
+        cache.record_rejections(&session_id, &rejected_now).await?;
+        summary.rejected += rejected_now.len();
+        hits = review.approved;
     }
 
     // set up a pretty progress bar
-    let pb = Arc::new(ProgressBar::new(hits.len() as u64));
+    let pb = Arc::new(PROGRESS.add(ProgressBar::new(hits.len() as u64)));
     pb.set_style(
-        ProgressStyle::with_template(
-            "[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} .DS_Store files destroyed",
-        )
-        .expect("Could not set up progress bar")
-        .progress_chars("=> "),
+        ProgressStyle::with_template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .expect("Could not set up progress bar")
+            .progress_chars("=> "),
     );
+    pb.set_message(format!("{files} destroyed"));
 
     // Track parent directories of deleted files and files that no longer exist
     let deleted_parents: Arc<Mutex<HashSet<PathBuf>>> = Arc::new(Mutex::new(HashSet::new()));
     let missing_parents: Arc<Mutex<HashSet<PathBuf>>> = Arc::new(Mutex::new(HashSet::new()));
+    let (records_tx, mut records_rx) = tokio::sync::mpsc::unbounded_channel();
 
     // ...otherwise, destroy the .DS_Store (mwah-ha-ha)
-    hits.into_par_iter().for_each_with(
-        (pb.clone(), deleted_parents.clone(), missing_parents.clone()),
-        |(pb, deleted, missing), hit| {
-            if verbosity.is_verbose() {
-                eprintln!("Deleting {}", &hit.to_string_lossy());
-            }
-            match fs::remove_file(&hit) {
-                Ok(()) => {
-                    pb.inc(1);
-                    if let Some(parent) = hit.parent() {
-                        deleted
-                            .lock()
-                            .expect("Failed to acquire lock on deleted_parents")
-                            .insert(parent.to_path_buf());
-                    }
-                }
-                Err(err) => {
-                    if err.kind() == std::io::ErrorKind::NotFound {
-                        // File was already deleted (perhaps manually)
-                        if verbosity.is_verbose() {
-                            eprintln!("File no longer exists: {}", &hit.to_string_lossy());
+    let deleting = {
+        let (pb, deleted_parents, missing_parents) =
+            (pb.clone(), deleted_parents.clone(), missing_parents.clone());
+        let (session_span, session_id) = (session_span.clone(), session_id.clone());
+        tokio::task::spawn_blocking(move || {
+            hits.into_par_iter().for_each_with(
+                (pb, deleted_parents, missing_parents, records_tx),
+                |(pb, deleted, missing, records), Hit { path: hit, .. }| {
+                    let _session = session_span.enter();
+                    debug!(file = %hit.display(), "Deleting");
+                    // Size, owner and hash have to be read before the file is gone
+                    let record = DeletionRecord::capture(&hit, Some(&session_id));
+                    let result = fs::remove_file(&hit);
+                    // The receiving end only goes away if recording failed, which is
+                    // reported there
+                    let _ = records.send(record.with_result(&result));
+                    // Only a `.DS_Store` says anything about the directory's cache entry
+                    let parent = hit
+                        .parent()
+                        .filter(|_| hit.file_name().is_some_and(|name| name == ".DS_Store"));
+                    match result {
+                        Ok(()) => {
+                            pb.inc(1);
+                            if let Some(parent) = parent {
+                                deleted
+                                    .lock()
+                                    .expect("Failed to acquire lock on deleted_parents")
+                                    .insert(parent.to_path_buf());
+                            }
                         }
-                        // Still mark the parent directory as having its .DS_Store deleted
-                        if let Some(parent) = hit.parent() {
-                            missing
-                                .lock()
-                                .expect("Failed to acquire lock on missing_parents")
-                                .insert(parent.to_path_buf());
+                        Err(err) => {
+                            if err.kind() == std::io::ErrorKind::NotFound {
+                                // File was already deleted (perhaps manually)
+                                debug!(file = %hit.display(), "File no longer exists");
+                                // Still mark the parent directory as having its .DS_Store deleted
+                                if let Some(parent) = parent {
+                                    missing
+                                        .lock()
+                                        .expect("Failed to acquire lock on missing_parents")
+                                        .insert(parent.to_path_buf());
+                                }
+                            } else {
+                                warn!(file = %hit.display(), error = %err, "Could not delete file");
+                            }
                         }
-                    } else if verbosity.is_verbose() {
-                        eprintln!(
-                            "The file at {} could not be deleted due to permissions: {err}",
-                            hit.display()
-                        );
                     }
-                }
+                },
+            );
+        })
+    };
+
+    // Record every attempt in the audit log as it happens, whatever its outcome, so an
+    // interrupted run still accounts for the files it did delete
+    const AUDIT_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+    const AUDIT_FLUSH_BATCH: usize = 256;
+    let mut audit_log = Vec::new();
+    let mut pending = Vec::new();
+    let mut last_flush = Instant::now();
+    loop {
+        let finished = match tokio::time::timeout(AUDIT_FLUSH_INTERVAL, records_rx.recv()).await {
+            Ok(Some(record)) => {
+                pending.push(record);
+                false
             }
-        },
-    );
+            Ok(None) => true,
+            Err(_) => false,
+        };
+        if finished
+            || pending.len() >= AUDIT_FLUSH_BATCH
+            || last_flush.elapsed() >= AUDIT_FLUSH_INTERVAL
+        {
+            cache.record_deletions(&pending).await?;
+            audit_log.append(&mut pending);
+            last_flush = Instant::now();
+        }
+        if finished {
+            break;
+        }
+    }
+    deleting.await?;
 
     pb.finish();
 
+    for record in &audit_log {
+        match record.outcome {
+            DeletionOutcome::Deleted => {
+                summary.deleted += 1;
+                summary.bytes_reclaimed += record
+                    .size
+                    .and_then(|size| u64::try_from(size).ok())
+                    .unwrap_or(0);
+            }
+            DeletionOutcome::Missing => summary.missing += 1,
+            DeletionOutcome::Failed => summary.failed += 1,
+            // Only archives are rewritten, and they are recorded separately
+            DeletionOutcome::Rewritten => {}
+        }
+    }
+
     // Mark directories where we deleted files as completed with ds_store_deleted = true
     let mut all_affected_dirs = deleted_parents
         .lock()
@@ -1060,13 +1746,19 @@ pub async fn bye_bye_ds_stores(
     let dirs_to_mark: Vec<PathBuf> = all_affected_dirs.into_iter().collect();
     let dir_states_to_mark: Vec<DirectoryState> = dirs_to_mark
         .into_iter()
-        .map(|dir| DirectoryState {
-            path: dir,
-            last_searched_at: chrono::Utc::now().timestamp(),
-            search_completed: true,
-            ds_store_found: true,
-            ds_store_deleted: true,
-            error_message: None,
+        .map(|dir| {
+            // Deleting the file bumped the directory mtime, so re-stamp it afterwards
+            let stamp = DirectoryStamp::of(&dir).unwrap_or_default();
+            DirectoryState {
+                path: dir,
+                last_searched_at: chrono::Utc::now().timestamp(),
+                search_completed: true,
+                ds_store_found: true,
+                ds_store_deleted: true,
+                error_message: None,
+                mtime_ns: stamp.mtime_ns,
+                inode: stamp.inode,
+            }
         })
         .collect();
 
@@ -1080,17 +1772,55 @@ pub async fn bye_bye_ds_stores(
         let _ = cache.cleanup_old_entries().await;
     }
 
-    let parting_message = if *recursive {
-        format!(
-            "{num_hits} .DS_Store files have been triumphally vanquished in {} and its {searched_dirs} subdirectories.", search_parent.display(),
-        )
-    } else {
-        format!(
-            "{num_hits} .DS_Store files have been triumphally vanquished in {}.",
-            search_parent.display()
-        )
-    };
-    eprintln!("{parting_message}");
+    if options.archives == ArchiveMode::Clean {
+        // Rewriting and hashing a large archive would otherwise stall the runtime
+        let options = options.clone();
+        let session_span = session_span.clone();
+        let (cleaned, audit_log) = tokio::task::spawn_blocking(move || {
+            let _session = session_span.enter();
+            let audit_log = clean_archives(&mut summary, &options, verbosity);
+            (summary, audit_log)
+        })
+        .await?;
+        summary = cleaned;
+        cache.record_deletions(&audit_log).await?;
+    }
 
-    Ok(())
+    if !summary.volume_dirs.is_empty() {
+        let audit_log = remove_volume_dirs(&mut summary, options, verbosity)?;
+        cache.record_deletions(&audit_log).await?;
+    }
+
+    eprintln!(
+        "{} {files} have been triumphally vanquished in {}.",
+        summary.deleted,
+        describe_search(&roots, *recursive, searched_dirs)
+    );
+    if summary.failed > 0 {
+        eprintln!(
+            "{} files could not be deleted; `dds log` shows why.",
+            summary.failed
+        );
+    }
+
+    summary.duration = started.elapsed();
+    Ok(summary)
+}
+
+/// The search directory by name, or how many there were
+fn describe_roots(roots: &[PathBuf]) -> String {
+    match roots {
+        [root] => root.display().to_string(),
+        _ => format!("{} directories", roots.len()),
+    }
+}
+
+/// Where a run searched, for its closing message
+fn describe_search(roots: &[PathBuf], recursive: bool, searched_dirs: usize) -> String {
+    let place = describe_roots(roots);
+    match (recursive, roots.len()) {
+        (false, _) => place,
+        (true, 1) => format!("{place} and its {searched_dirs} subdirectories"),
+        (true, _) => format!("{place} and their {searched_dirs} subdirectories"),
+    }
 }
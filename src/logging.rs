@@ -0,0 +1,89 @@
+use color_eyre::eyre::{eyre, Result};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::config::{Config, LogRotation};
+use crate::{Verbosity, PROGRESS};
+
+/// Environment variable holding `tracing` filter directives for terminal output
+pub const LOG_ENV_VAR: &str = "DDS_LOG";
+
+/// Default file filter: everything from `dds` at debug, and only warnings from dependencies
+const DEFAULT_FILE_FILTER: &str = "warn,dds=debug";
+
+/// Writes to stderr with any progress bars hidden, so log lines never tear the spinner
+struct ProgressAwareStderr;
+
+impl Write for ProgressAwareStderr {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        PROGRESS.suspend(|| std::io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
+
+/// Install the global `tracing` subscriber
+///
+/// Terminal output follows `--verbose`/`--quiet` unless [`LOG_ENV_VAR`] overrides it with
+/// per-module directives. When a log directory is configured, a second layer writes
+/// everything allowed by `log_filter` there as JSON lines. The returned guard flushes
+/// the file writer and must be kept alive until the program exits.
+pub fn init(
+    verbosity: Verbosity,
+    config: &Config,
+    log_dir: Option<&Path>,
+) -> Result<Option<WorkerGuard>> {
+    let console_filter = match std::env::var(LOG_ENV_VAR) {
+        Ok(directives) => EnvFilter::try_new(directives)
+            .map_err(|e| eyre!("Invalid {LOG_ENV_VAR} filter: {e}"))?,
+        Err(_) => EnvFilter::new(match verbosity {
+            Verbosity::Quiet => "error",
+            Verbosity::Normal => "warn",
+            Verbosity::Verbose => "warn,dds=debug",
+        }),
+    };
+    let console = fmt::layer()
+        .with_writer(|| ProgressAwareStderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .without_time()
+        .with_target(false)
+        .with_filter(console_filter);
+
+    let (file, guard) = match log_dir.or(config.log_dir.as_deref()) {
+        Some(dir) => {
+            let file_filter =
+                EnvFilter::try_new(config.log_filter.as_deref().unwrap_or(DEFAULT_FILE_FILTER))
+                    .map_err(|e| eyre!("Invalid log_filter in config: {e}"))?;
+            let rotation = match config.log_rotation {
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+                LogRotation::Never => Rotation::NEVER,
+            };
+            std::fs::create_dir_all(dir)?;
+            let (writer, guard) =
+                tracing_appender::non_blocking(RollingFileAppender::new(rotation, dir, "dds.log"));
+            // JSON lines keep span fields (session id, directory) machine-readable
+            let layer = fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(writer)
+                .with_filter(file_filter);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(file)
+        .try_init()
+        .map_err(|e| eyre!("Could not set up logging: {e}"))?;
+
+    Ok(guard)
+}
@@ -7,9 +7,25 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use color_eyre::eyre::Result;
-use dds::{bye_bye_ds_stores, cache::Cache, cli::Cli, config::Config, Verbosity};
+use dds::{
+    appledouble::AppleDoubleMode,
+    archives::ArchiveMode,
+    bye_bye_ds_stores_in,
+    cache::{Cache, DeletionOutcome, DeletionQuery, ForgetSummary, SearchSession},
+    cli::{CacheCommand, Cli, Command, ReportCommand},
+    config::Config,
+    filters::HitFilter,
+    logging, metrics, migrations,
+    ownership::{self, OwnerFilter},
+    portable::{self, PortableFormat},
+    profiles::ProfileSet,
+    report::{self, ReportFormat, TrendPeriod},
+    roots, safety,
+    volume::VolumeCleanup,
+    RunOptions, RunSummary, Verbosity,
+};
 use tokio::sync::Mutex;
 
 #[tokio::main]
@@ -18,10 +34,21 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Load config and initialize cache
-    let config = Config::load().await?;
+    // Load config and initialize cache; ephemeral runs must not create `~/.dds`
+    let config = if cli.no_cache {
+        Config::load_read_only().await?
+    } else {
+        Config::load().await?
+    };
     let cache_hours = cli.cache_hours.unwrap_or(config.cache_window_hours);
 
+    // Keep the guard alive so buffered log lines reach the file before exit
+    let _log_guard = logging::init(
+        Verbosity::new_from_bools(cli.verbose, cli.quiet),
+        &config,
+        cli.log_dir.as_deref(),
+    )?;
+
     // Handle cache management commands
     if cli.cache_status {
         return handle_cache_status(&config.database_path, cache_hours).await;
@@ -35,22 +62,171 @@ async fn main() -> Result<()> {
         return handle_cache_stats(&config.database_path, cache_hours).await;
     }
 
+    if cli.no_cache && cli.command.is_some() {
+        return Err(color_eyre::eyre::eyre!(
+            "`dds cache`, `dds log` and `dds report` read the on-disk cache and cannot be combined with --no-cache"
+        ));
+    }
+
+    if let Some(Command::Log {
+        path,
+        since,
+        until,
+        limit,
+    }) = &cli.command
+    {
+        let query = DeletionQuery {
+            prefix: path.as_deref().map(resolve_dir).transpose()?,
+            since: since.map(start_of_day),
+            // `--until` names the last day to include
+            until: until.and_then(|day| day.succ_opt()).map(start_of_day),
+            limit: *limit,
+            ..Default::default()
+        };
+        return handle_log(&config.database_path, cache_hours, &query, cli.verbose).await;
+    }
+
+    if let Some(Command::Report { action }) = &cli.command {
+        return match action {
+            ReportCommand::Trends { path, by, top } => {
+                let root = path.as_deref().map(resolve_dir).transpose()?;
+                handle_report_trends(
+                    &config.database_path,
+                    cache_hours,
+                    root.as_deref(),
+                    *by,
+                    *top,
+                )
+                .await
+            }
+            ReportCommand::Session {
+                session,
+                format,
+                output,
+            } => {
+                handle_report_session(
+                    &config.database_path,
+                    cache_hours,
+                    session.as_deref(),
+                    *format,
+                    output.as_deref(),
+                )
+                .await
+            }
+            ReportCommand::Tree {
+                session,
+                depth,
+                min_hits,
+            } => {
+                handle_report_tree(
+                    &config.database_path,
+                    cache_hours,
+                    session.as_deref(),
+                    *depth,
+                    *min_hits,
+                )
+                .await
+            }
+        };
+    }
+
+    if let Some(Command::Cache { action }) = &cli.command {
+        return match action {
+            CacheCommand::Export { file, format } => {
+                handle_cache_export(&config.database_path, cache_hours, file, *format).await
+            }
+            CacheCommand::Import { file, format } => {
+                handle_cache_import(&config.database_path, cache_hours, file, *format).await
+            }
+            CacheCommand::Forget { path } => {
+                handle_cache_forget(&config.database_path, cache_hours, &resolve_dir(path)?).await
+            }
+        };
+    }
+
     // Normal operation - search for .DS_Store files
-    let search_parent = match cli.dir.as_str() {
-        "." => std::env::current_dir()?,
-        _ => PathBuf::from(&cli.dir),
+    let mut dir_args = cli.dir.clone();
+    if let Some(file) = &cli.roots_from {
+        let listed = if file.as_os_str() == "-" {
+            roots::read_roots(std::io::stdin().lock())?
+        } else {
+            roots::read_roots(std::io::BufReader::new(std::fs::File::open(file)?))?
+        };
+        if listed.is_empty() && dir_args.is_empty() {
+            return Err(color_eyre::eyre::eyre!(
+                "No directories to search were listed in {}",
+                file.display()
+            ));
+        }
+        dir_args.extend(listed);
+    }
+    if dir_args.is_empty() {
+        dir_args.push(".".to_string());
+    }
+    let search_roots = roots::dedupe(
+        dir_args
+            .iter()
+            .map(|dir| resolve_dir(dir))
+            .collect::<Result<Vec<_>>>()?,
+        cli.recursive,
+    );
+
+    // `--report FORMAT FILE`; check the format before spending time on the search
+    let report_output = match cli.report.as_deref() {
+        Some([format, path]) => Some((
+            ReportFormat::from_str(format, true).map_err(|_| {
+                color_eyre::eyre::eyre!("Unknown report format `{format}`; expected html or csv")
+            })?,
+            PathBuf::from(path),
+        )),
+        _ => None,
     };
 
-    // check to make sure the provided search directory exists
-    assert!(
-        search_parent.is_dir(),
-        "The provided search directory, {}, does not exist on the user's system or is outside of user permissions",
-        search_parent.display()
-    );
+    // check to make sure the provided search directories exist
+    if let Some(missing) = search_roots.iter().find(|root| !root.is_dir()) {
+        return Err(color_eyre::eyre::eyre!(
+            "The provided search directory, {}, does not exist on the user's system or is outside of user permissions",
+            missing.display()
+        ));
+    }
 
-    let cache = Arc::new(Mutex::new(
-        Cache::new(&config.database_path, cache_hours, cli.force).await?,
-    ));
+    let mut owners = OwnerFilter {
+        uids: cli
+            .owner
+            .iter()
+            .map(|user| ownership::resolve_user(user))
+            .collect::<Result<_>>()?,
+        gids: cli
+            .group
+            .iter()
+            .map(|group| ownership::resolve_group(group))
+            .collect::<Result<_>>()?,
+    };
+    if cli.mine {
+        owners.uids.push(ownership::current_uid()?);
+    }
+    let profiles = ProfileSet::resolve(&cli.profile, &config.profiles)?;
+
+    // A mistyped root could clear every user's home; make people say they mean it
+    if !cli.dry && !cli.yes_really {
+        for search_parent in &search_roots {
+            if let Some(reason) =
+                safety::broad_root_reason(search_parent, dirs::home_dir().as_deref())
+            {
+                return Err(color_eyre::eyre::eyre!(
+                    "{} is {reason}; pass --yes-really to delete .DS_Store files there",
+                    search_parent.display()
+                ));
+            }
+        }
+    }
+
+    let cache = if cli.no_cache {
+        Cache::in_memory(cache_hours, cli.force).await?
+    } else {
+        Cache::new(&config.database_path, cache_hours, cli.force).await?
+    };
+    let cache = Arc::new(Mutex::new(cache));
 
     // separate out the two other runtime settings
     let recursive = &cli.recursive;
@@ -93,36 +269,380 @@ async fn main() -> Result<()> {
     // do away with .DS_Store files based on those settings
     let result = {
         let mut cache_guard = cache.lock().await;
-        bye_bye_ds_stores(
-            &search_parent,
+        bye_bye_ds_stores_in(
+            &search_roots,
             recursive,
             verbosity,
             dryrun,
-            &mut cache_guard,
+            &mut *cache_guard,
+            &RunOptions {
+                interactive: cli.interactive,
+                max_delete: cli.max_delete.or(config.max_delete),
+                protected_paths: safety::resolve_protected(&config.protected_paths),
+                owners,
+                filter: HitFilter {
+                    older_than: cli.older_than,
+                    newer_than: cli.newer_than,
+                    min_size: cli.min_size,
+                    max_size: cli.max_size,
+                },
+                appledouble: AppleDoubleMode::from_flags(cli.appledouble, cli.appledouble_paired),
+                archives: if cli.clean_archives {
+                    ArchiveMode::Clean
+                } else if cli.archives {
+                    ArchiveMode::Report
+                } else {
+                    ArchiveMode::Off
+                },
+                archive_backup: cli.archive_backup,
+                volume_cleanup: match (cli.volume_cleanup, cli.confirm_volume_cleanup) {
+                    (false, _) => VolumeCleanup::Off,
+                    (true, false) => VolumeCleanup::Ask,
+                    (true, true) => VolumeCleanup::Confirmed,
+                },
+                profiles,
+            },
             cancellation_token,
         )
         .await
     };
 
+    if let (Ok(summary), true) = (&result, cli.usage) {
+        print_usage(summary);
+    }
+    if let (Ok(summary), true) = (&result, cli.tree) {
+        let tree = report::hit_tree(
+            &summary.root,
+            summary.hits.iter().map(|hit| hit.path.as_path()),
+            Some(cli.tree_depth),
+            cli.tree_min_hits,
+        );
+        println!();
+        print!("{}", report::render_tree(&tree));
+    }
+
+    if let (Ok(summary), Some((format, path))) = (&result, &report_output) {
+        let cache_guard = cache.lock().await;
+        let session_report =
+            report::session_report(&cache_guard, &summary.session_id, Some(summary)).await?;
+        report::write_session_report(path, *format, &session_report)?;
+        if verbosity.is_not_quiet() {
+            eprintln!("Report written to {}", path.display());
+        }
+    }
+
+    let metrics_file = cli.metrics_file.as_ref().or(config.metrics_file.as_ref());
+    if let (Ok(summary), Some(metrics_file)) = (&result, metrics_file) {
+        let cache_guard = cache.lock().await;
+        let contents = metrics::render(
+            summary,
+            Some(&cache_guard.get_cache_stats().await?),
+            // An ephemeral cache takes no space worth reporting
+            if cli.no_cache {
+                None
+            } else {
+                Some(cache_guard.database_size_bytes().await?)
+            },
+            chrono::Utc::now().timestamp(),
+        );
+        metrics::write_textfile(metrics_file, &contents)?;
+    }
+
     // Ensure cache is dropped before returning
     drop(cache);
 
     // Cancel the signal handler since we're exiting normally
     shutdown_handle.abort();
 
-    result
+    result.map(|_| ())
+}
+
+/// Map a directory argument to the path used as a cache key
+fn resolve_dir(dir: &str) -> Result<PathBuf> {
+    Ok(match dir {
+        "." => std::env::current_dir()?,
+        // Relative directories are made absolute so nested roots can be told apart
+        _ => std::env::current_dir()?.join(dir).components().collect(),
+    })
+}
+
+/// Unix timestamp of midnight UTC at the start of `day`
+fn start_of_day(day: chrono::NaiveDate) -> i64 {
+    day.and_time(chrono::NaiveTime::MIN).and_utc().timestamp()
+}
+
+async fn handle_log(
+    database_path: &Path,
+    cache_hours: u64,
+    query: &DeletionQuery,
+    verbose: bool,
+) -> Result<()> {
+    let cache = Cache::new(database_path, cache_hours, false).await?;
+    let records = cache.get_deletions(query).await?;
+
+    if records.is_empty() {
+        println!("No deletions recorded.");
+        return Ok(());
+    }
+
+    println!("Deletion Log");
+    println!("============");
+    for record in &records {
+        let attempted_at = chrono::DateTime::from_timestamp(record.attempted_at, 0).map_or_else(
+            || record.attempted_at.to_string(),
+            |at| at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        );
+        let size = record
+            .size
+            .map_or_else(|| "-".to_string(), |size| format!("{size} B"));
+        println!(
+            "{attempted_at}  {:<9}  {size:>9}  {}",
+            record.outcome.as_str(),
+            record.file_path.display()
+        );
+
+        if record.outcome == DeletionOutcome::Failed {
+            if let Some(error) = &record.error_message {
+                println!("    error: {error}");
+            }
+        }
+        if verbose {
+            println!(
+                "    session: {}  owner uid: {}  sha256: {}",
+                record.session_id.as_deref().unwrap_or("-"),
+                record
+                    .owner_uid
+                    .map_or_else(|| "-".to_string(), |uid| uid.to_string()),
+                record.content_hash.as_deref().unwrap_or("-")
+            );
+        }
+    }
+    println!();
+    println!("Entries shown: {} (times in UTC)", records.len());
+
+    Ok(())
+}
+
+/// How many of the largest `.DS_Store` files `--usage` lists
+const USAGE_TOP: usize = 10;
+
+fn print_usage(summary: &RunSummary) {
+    let usage = report::usage(
+        &summary.root,
+        &summary.hits,
+        summary.bytes_reclaimed,
+        USAGE_TOP,
+    );
+
+    println!();
+    println!("Disk Usage");
+    println!("==========");
+    println!(
+        "Total:      {} in {} files",
+        report::format_bytes(usage.total_bytes),
+        usage.total_files
+    );
+    if usage.unsized_files > 0 {
+        println!(
+            "            ({} files could not be sized)",
+            usage.unsized_files
+        );
+    }
+    if summary.dry_run {
+        println!("Reclaimed:  none (dry run)");
+    } else {
+        println!(
+            "Reclaimed:  {}",
+            report::format_bytes(usage.reclaimed_bytes)
+        );
+    }
+
+    if !usage.largest.is_empty() {
+        println!();
+        println!("Largest files:");
+        for hit in &usage.largest {
+            println!(
+                "  {:>10}  {}",
+                report::format_bytes(hit.size.unwrap_or(0)),
+                hit.path.display()
+            );
+        }
+    }
+
+    if !usage.subtotals.is_empty() {
+        println!();
+        println!("By directory:");
+        for entry in &usage.subtotals {
+            println!(
+                "  {:>10}  {:>7} files  {}",
+                report::format_bytes(entry.bytes),
+                entry.files,
+                entry.path.display()
+            );
+        }
+    }
+}
+
+/// Look up a session by id, or take the most recent one when no id is given
+async fn find_session(cache: &Cache, session_id: Option<&str>) -> Result<Option<SearchSession>> {
+    let mut sessions = cache.get_sessions().await?;
+    match session_id {
+        Some(id) => sessions
+            .into_iter()
+            .find(|session| session.session_id == id)
+            .map(Some)
+            .ok_or_else(|| color_eyre::eyre::eyre!("No search session with id {id}")),
+        None => Ok(sessions.pop()),
+    }
+}
+
+async fn handle_report_session(
+    database_path: &Path,
+    cache_hours: u64,
+    session_id: Option<&str>,
+    format: ReportFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let cache = Cache::new(database_path, cache_hours, false).await?;
+    let Some(session) = find_session(&cache, session_id).await? else {
+        println!("No search history recorded yet.");
+        return Ok(());
+    };
+
+    let session_report = report::session_report(&cache, &session.session_id, None).await?;
+    let output = output.map_or_else(
+        || report::default_file_name(&session.session_id, format),
+        Path::to_path_buf,
+    );
+    report::write_session_report(&output, format, &session_report)?;
+    println!(
+        "Report for session {} written to {}",
+        session.session_id,
+        output.display()
+    );
+
+    Ok(())
+}
+
+async fn handle_report_tree(
+    database_path: &Path,
+    cache_hours: u64,
+    session_id: Option<&str>,
+    depth: usize,
+    min_hits: u64,
+) -> Result<()> {
+    let cache = Cache::new(database_path, cache_hours, false).await?;
+    let Some(session) = find_session(&cache, session_id).await? else {
+        println!("No search history recorded yet.");
+        return Ok(());
+    };
+
+    let hits = cache.load_found_files(&session.session_id).await?;
+    println!("Session {} ({} hits)", session.session_id, hits.len());
+    println!();
+    let tree = report::hit_tree(
+        &session.root_path,
+        hits.iter().map(PathBuf::as_path),
+        Some(depth),
+        min_hits,
+    );
+    print!("{}", report::render_tree(&tree));
+
+    Ok(())
+}
+
+async fn handle_report_trends(
+    database_path: &Path,
+    cache_hours: u64,
+    root: Option<&Path>,
+    period: TrendPeriod,
+    top: usize,
+) -> Result<()> {
+    let cache = Cache::new(database_path, cache_hours, false).await?;
+    let trends = report::trends(&cache, root, period, top).await?;
+
+    if trends.buckets.is_empty() {
+        println!("No search history recorded yet.");
+        return Ok(());
+    }
+
+    let period_label = match period {
+        TrendPeriod::Day => "day",
+        TrendPeriod::Week => "week",
+    };
+    println!("Cleanup Trends (by {period_label}, UTC)");
+    println!("==============================");
+
+    let mut current_root = None;
+    for bucket in &trends.buckets {
+        if current_root != Some(&bucket.root) {
+            println!();
+            println!("{}", bucket.root.display());
+            println!("  Period starting    Found  Deleted");
+            current_root = Some(&bucket.root);
+        }
+        println!(
+            "  {}      {:>7}  {:>7}",
+            bucket.period_start, bucket.found, bucket.deleted
+        );
+    }
+
+    println!();
+    if trends.reinfected.is_empty() {
+        println!("No cleaned directories have been re-infected.");
+    } else {
+        println!("Most re-infected directories:");
+        for directory in &trends.reinfected {
+            println!(
+                "  {:>5}  {}",
+                directory.reinfections,
+                directory.path.display()
+            );
+        }
+    }
+
+    if let Some(mean) = trends.mean_reinfection_secs {
+        println!();
+        println!(
+            "Mean time to re-infection: {} (over {} re-infections)",
+            report::format_duration(mean),
+            trends.reinfection_count
+        );
+    }
+
+    Ok(())
 }
 
 async fn handle_cache_status(database_path: &Path, cache_hours: u64) -> Result<()> {
     let cache = Cache::new(database_path, cache_hours, false).await?;
     let incomplete = cache.get_incomplete_searches().await?;
 
+    let schema_version = cache.schema_version().await?.unwrap_or(0);
+
     println!("Cache Status");
     println!("============");
     println!("Database: {}", database_path.display());
+    println!(
+        "Schema version: {schema_version} (latest {})",
+        migrations::latest_version()
+    );
     println!("Cache window: {cache_hours} hours");
     println!();
 
+    let live_sessions = cache.get_live_sessions().await?;
+    if !live_sessions.is_empty() {
+        println!("Running searches ({} total):", live_sessions.len());
+        for (session, owner_pid) in live_sessions {
+            println!(
+                "  - {} (session {}, pid {})",
+                session.root_path.display(),
+                session.session_id,
+                owner_pid.map_or_else(|| "unknown".to_string(), |pid| pid.to_string())
+            );
+        }
+        println!();
+    }
+
     if incomplete.is_empty() {
         println!("No incomplete searches found.");
     } else {
@@ -178,3 +698,75 @@ async fn handle_cache_stats(database_path: &Path, cache_hours: u64) -> Result<()
 
     Ok(())
 }
+
+async fn handle_cache_export(
+    database_path: &Path,
+    cache_hours: u64,
+    file: &Path,
+    format: Option<PortableFormat>,
+) -> Result<()> {
+    let cache = Cache::new(database_path, cache_hours, false).await?;
+    let records = cache.export_records().await?;
+    let format = format.unwrap_or_else(|| PortableFormat::from_path(file));
+
+    if file == Path::new("-") {
+        portable::write_records(std::io::stdout().lock(), format, &records)?;
+    } else {
+        let writer = std::io::BufWriter::new(std::fs::File::create(file)?);
+        portable::write_records(writer, format, &records)?;
+        eprintln!(
+            "Exported {} cache records to {}",
+            records.len(),
+            file.display()
+        );
+    }
+
+    Ok(())
+}
+
+async fn handle_cache_import(
+    database_path: &Path,
+    cache_hours: u64,
+    file: &Path,
+    format: Option<PortableFormat>,
+) -> Result<()> {
+    let format = format.unwrap_or_else(|| PortableFormat::from_path(file));
+    let records = if file == Path::new("-") {
+        portable::read_records(std::io::stdin().lock(), format)?
+    } else {
+        portable::read_records(std::fs::File::open(file)?, format)?
+    };
+    let total = records.len();
+
+    let mut cache = Cache::new(database_path, cache_hours, false).await?;
+    let summary = cache.import_records(records).await?;
+
+    println!("Cache Import");
+    println!("============");
+    println!("Records read:                 {total}");
+    println!("Directories merged:           {}", summary.directories);
+    println!("Sessions merged:              {}", summary.sessions);
+    println!("Found files added:            {}", summary.found_files);
+
+    Ok(())
+}
+
+async fn handle_cache_forget(database_path: &Path, cache_hours: u64, path: &Path) -> Result<()> {
+    let mut cache = Cache::new(database_path, cache_hours, false).await?;
+    let summary = cache.forget_subtree(path).await?;
+
+    if summary == ForgetSummary::default() {
+        println!("Nothing cached under {}.", path.display());
+    } else {
+        println!(
+            "Forgot {} directories, {} found files, {} sessions and {} rejected files under {}.",
+            summary.directories,
+            summary.found_files,
+            summary.sessions,
+            summary.rejections,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
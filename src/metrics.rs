@@ -0,0 +1,154 @@
+use color_eyre::eyre::{eyre, Result};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::cache::CacheStats;
+use crate::RunSummary;
+
+/// Render a run as an OpenMetrics text exposition for node_exporter's textfile collector
+///
+/// Every per-run series carries a `root` label, so runs against different roots can
+/// write separate files into the same collector directory without clashing.
+#[must_use]
+pub fn render(
+    summary: &RunSummary,
+    cache_stats: Option<&CacheStats>,
+    cache_size_bytes: Option<u64>,
+    finished_at: i64,
+) -> String {
+    let root = escape_label(&summary.root.to_string_lossy());
+    let mut out = String::new();
+
+    let mut gauge = |name: &str, help: &str, samples: &[(&str, f64)]| {
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        let _ = writeln!(out, "# HELP {name} {help}");
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{name}{{root=\"{root}\"{labels}}} {value}");
+        }
+    };
+
+    gauge(
+        "dds_last_run_timestamp_seconds",
+        "Unix time at which the last run finished.",
+        &[("", finished_at as f64)],
+    );
+    gauge(
+        "dds_last_run_duration_seconds",
+        "Wall-clock duration of the last run.",
+        &[("", summary.duration.as_secs_f64())],
+    );
+    gauge(
+        "dds_last_run_dry_run",
+        "Whether the last run was a dry run (1) or deleted files (0).",
+        &[("", f64::from(u8::from(summary.dry_run)))],
+    );
+    gauge(
+        "dds_last_run_hits_found",
+        ".DS_Store files found by the last run.",
+        &[("", summary.hits_found as f64)],
+    );
+    gauge(
+        "dds_last_run_bytes",
+        "Combined size of the .DS_Store files found and deleted by the last run.",
+        &[
+            (",kind=\"found\"", summary.bytes_found as f64),
+            (",kind=\"reclaimed\"", summary.bytes_reclaimed as f64),
+        ],
+    );
+    gauge(
+        "dds_last_run_hits_kept",
+        "Hits the last run found but deliberately did not delete, by reason.",
+        &[
+            (",reason=\"filtered\"", summary.skipped_filtered as f64),
+            (",reason=\"ownership\"", summary.skipped_ownership as f64),
+            (",reason=\"paired\"", summary.skipped_paired as f64),
+            (",reason=\"protected\"", summary.protected as f64),
+            (",reason=\"rejected\"", summary.rejected as f64),
+        ],
+    );
+    gauge(
+        "dds_last_run_archives",
+        "Archives the last run found holding macOS entries, and what became of them.",
+        &[
+            (",state=\"found\"", summary.archives.len() as f64),
+            (",state=\"cleaned\"", summary.archives_cleaned as f64),
+            (",state=\"failed\"", summary.archives_failed as f64),
+        ],
+    );
+    gauge(
+        "dds_last_run_volume_dirs",
+        "macOS metadata directories the last run found at the volume root, and what became of them.",
+        &[
+            (",state=\"found\"", summary.volume_dirs.len() as f64),
+            (",state=\"removed\"", summary.volume_dirs_removed as f64),
+            (",state=\"failed\"", summary.volume_dirs_failed as f64),
+        ],
+    );
+    gauge(
+        "dds_last_run_deletions",
+        "Deletion attempts made by the last run, by outcome.",
+        &[
+            (",outcome=\"deleted\"", summary.deleted as f64),
+            (",outcome=\"missing\"", summary.missing as f64),
+            (",outcome=\"failed\"", summary.failed as f64),
+        ],
+    );
+    gauge(
+        "dds_last_run_directories",
+        "Directories visited by the last run, by what happened to them.",
+        &[
+            (",state=\"searched\"", summary.directories_searched as f64),
+            (",state=\"resumed\"", summary.directories_resumed as f64),
+            (",state=\"skipped\"", summary.directories_skipped as f64),
+            (",state=\"errored\"", summary.directories_errored as f64),
+        ],
+    );
+
+    if let Some(stats) = cache_stats {
+        gauge(
+            "dds_cache_entries",
+            "Directories recorded in the cache database, by state.",
+            &[
+                (",state=\"completed\"", stats.completed_searches as f64),
+                (",state=\"incomplete\"", stats.incomplete_searches as f64),
+                (",state=\"errored\"", stats.errors as f64),
+            ],
+        );
+    }
+    if let Some(bytes) = cache_size_bytes {
+        gauge(
+            "dds_cache_size_bytes",
+            "Size of the cache database on disk.",
+            &[("", bytes as f64)],
+        );
+    }
+
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Write `contents` to `path` so the collector never reads a half-written file
+///
+/// The file is written next to its destination and renamed into place, as the textfile
+/// collector documentation recommends.
+pub fn write_textfile(path: &Path, contents: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| eyre!("Metrics path {} has no file name", path.display()))?;
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        eyre!("Could not move metrics into {}: {e}", path.display())
+    })
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
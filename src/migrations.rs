@@ -0,0 +1,324 @@
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Result};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use tracing::info;
+
+/// A single forward-only schema change
+///
+/// Migrations are applied in order inside their own transaction, and the version is
+/// recorded in `schema_version` in that same transaction, so a failed upgrade leaves
+/// the database at the last version that applied cleanly.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    statements: &'static [&'static str],
+}
+
+/// Every schema change the cache has gone through, oldest first.
+/// Append new migrations to the end; never edit one that has shipped.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "directory cache, work queue, search sessions and found files",
+        statements: &[
+            r"
+            CREATE TABLE IF NOT EXISTS directory_cache (
+                path TEXT PRIMARY KEY,
+                last_searched_at INTEGER NOT NULL,
+                search_completed BOOLEAN NOT NULL DEFAULT FALSE,
+                ds_store_found BOOLEAN NOT NULL DEFAULT FALSE,
+                ds_store_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+                error_message TEXT
+            )
+            ",
+            r"
+            CREATE TABLE IF NOT EXISTS work_queue (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                path TEXT NOT NULL,
+                discovered_at INTEGER NOT NULL,
+                priority INTEGER NOT NULL DEFAULT 0,
+                session_id TEXT,
+                UNIQUE(path, session_id)
+            )
+            ",
+            r"
+            CREATE TABLE IF NOT EXISTS search_sessions (
+                session_id TEXT PRIMARY KEY,
+                root_path TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                completed_at INTEGER,
+                is_recursive BOOLEAN NOT NULL,
+                is_dry_run BOOLEAN NOT NULL,
+                status TEXT NOT NULL DEFAULT 'active'
+            )
+            ",
+            r"
+            CREATE TABLE IF NOT EXISTS found_files (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                discovered_at INTEGER NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES search_sessions(session_id),
+                UNIQUE(session_id, file_path)
+            )
+            ",
+            "CREATE INDEX IF NOT EXISTS idx_last_searched ON directory_cache(last_searched_at)",
+            "CREATE INDEX IF NOT EXISTS idx_incomplete ON directory_cache(search_completed) WHERE search_completed = FALSE",
+            "CREATE INDEX IF NOT EXISTS idx_fresh_complete ON directory_cache(last_searched_at, search_completed) WHERE search_completed = TRUE",
+            "CREATE INDEX IF NOT EXISTS idx_queue_session ON work_queue(session_id, priority, id)",
+            "CREATE INDEX IF NOT EXISTS idx_queue_path ON work_queue(path)",
+            "CREATE INDEX IF NOT EXISTS idx_sessions_status ON search_sessions(status, started_at)",
+            "CREATE INDEX IF NOT EXISTS idx_found_files_session ON found_files(session_id)",
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "directory mtime/inode stamps and parent paths",
+        statements: &[
+            "ALTER TABLE directory_cache ADD COLUMN dir_mtime_ns INTEGER",
+            "ALTER TABLE directory_cache ADD COLUMN dir_inode INTEGER",
+            "ALTER TABLE directory_cache ADD COLUMN parent_path TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_parent_path ON directory_cache(parent_path)",
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "session owner pid and heartbeat for cross-process coordination",
+        statements: &[
+            "ALTER TABLE search_sessions ADD COLUMN owner_pid INTEGER",
+            "ALTER TABLE search_sessions ADD COLUMN heartbeat_at INTEGER",
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "audit log of attempted deletions",
+        statements: &[
+            r"
+            CREATE TABLE IF NOT EXISTS deletions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                file_path TEXT NOT NULL,
+                size INTEGER,
+                mtime INTEGER,
+                owner_uid INTEGER,
+                content_hash TEXT,
+                session_id TEXT,
+                outcome TEXT NOT NULL,
+                error_message TEXT,
+                attempted_at INTEGER NOT NULL
+            )
+            ",
+            "CREATE INDEX IF NOT EXISTS idx_deletions_attempted ON deletions(attempted_at)",
+            "CREATE INDEX IF NOT EXISTS idx_deletions_path ON deletions(file_path)",
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "size of each found file",
+        statements: &["ALTER TABLE found_files ADD COLUMN size INTEGER"],
+    },
+    Migration {
+        version: 6,
+        description: "files rejected during interactive review",
+        statements: &[r"
+            CREATE TABLE IF NOT EXISTS rejections (
+                file_path TEXT PRIMARY KEY,
+                session_id TEXT,
+                rejected_at INTEGER NOT NULL
+            )
+            "],
+    },
+    Migration {
+        version: 7,
+        description: "cleanup profiles each search session was run with",
+        statements: &["ALTER TABLE search_sessions ADD COLUMN profiles TEXT NOT NULL DEFAULT ''"],
+    },
+    Migration {
+        version: 8,
+        description: "search directories of sessions run over more than one",
+        statements: &["ALTER TABLE search_sessions ADD COLUMN roots TEXT NOT NULL DEFAULT ''"],
+    },
+    Migration {
+        version: 9,
+        description: "parent paths of directories cached before they were recorded",
+        // `rtrim` with every character but `/` strips the last component, leaving its
+        // parent and a trailing slash that is dropped unless the parent is `/`
+        statements: &[r"
+            UPDATE directory_cache SET parent_path = CASE
+                WHEN path = '/' THEN NULL
+                WHEN rtrim(path, replace(path, '/', '')) = '/' THEN '/'
+                ELSE substr(
+                    rtrim(path, replace(path, '/', '')),
+                    1,
+                    length(rtrim(path, replace(path, '/', ''))) - 1
+                )
+            END
+            WHERE parent_path IS NULL
+            "],
+    },
+];
+
+/// The schema version this build of `dds` writes
+#[must_use]
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Read the schema version recorded in the database, if it has been versioned yet
+pub async fn current_version(pool: &SqlitePool) -> Result<Option<i64>> {
+    if !table_exists(&mut *pool.acquire().await?, "schema_version").await? {
+        return Ok(None);
+    }
+    let row = sqlx::query("SELECT MAX(version) AS version FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.get("version"))
+}
+
+/// Bring the database up to [`latest_version`], refusing to touch newer databases
+pub async fn run(pool: &SqlitePool) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+
+    sqlx::query(
+        r"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL
+        )
+        ",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let recorded: Option<i64> = sqlx::query("SELECT MAX(version) AS version FROM schema_version")
+        .fetch_one(&mut *conn)
+        .await?
+        .get("version");
+
+    let current = match recorded {
+        Some(version) => version,
+        None => {
+            // Databases written before versioning existed carry their version implicitly
+            let detected = detect_unversioned(&mut conn).await?;
+            if detected > 0 {
+                record_version(&mut conn, detected).await?;
+            }
+            detected
+        }
+    };
+
+    let latest = latest_version();
+    if current > latest {
+        return Err(eyre!(
+            "The cache database uses schema version {current}, but this version of dds only \
+             understands up to version {latest}. Upgrade dds, or point `database_path` in \
+             ~/.dds/config.toml at a different file."
+        ));
+    }
+
+    let legacy = table_exists(&mut conn, "searched_dirs").await?;
+    let pending: Vec<&Migration> = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current)
+        .collect();
+
+    if legacy || (current > 0 && !pending.is_empty()) {
+        info!(
+            from = current,
+            to = latest,
+            "Upgrading cache database schema"
+        );
+    }
+
+    for migration in pending {
+        // Take the write lock before looking at the version, so a second `dds` starting
+        // on the same database waits for this one and then finds the work done
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+        match apply(&mut conn, migration).await {
+            Ok(()) => {
+                sqlx::query("COMMIT").execute(&mut *conn).await?;
+            }
+            Err(err) => {
+                sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply one migration inside the caller's transaction, unless another process already has
+async fn apply(conn: &mut SqliteConnection, migration: &Migration) -> Result<()> {
+    let recorded: Option<i64> = sqlx::query("SELECT MAX(version) AS version FROM schema_version")
+        .fetch_one(&mut *conn)
+        .await?
+        .get("version");
+    if recorded.is_some_and(|version| version >= migration.version) {
+        return Ok(());
+    }
+
+    for statement in migration.statements {
+        sqlx::query(statement)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| {
+                eyre!(
+                    "Schema migration {} ({}) failed: {e}",
+                    migration.version,
+                    migration.description
+                )
+            })?;
+    }
+
+    // The original single-table cache is folded into the first versioned schema
+    if migration.version == 1 && table_exists(conn, "searched_dirs").await? {
+        sqlx::query(
+            r"
+            INSERT OR IGNORE INTO directory_cache (path, last_searched_at, search_completed)
+            SELECT path, last_searched_at, TRUE FROM searched_dirs
+            ",
+        )
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query("DROP TABLE searched_dirs")
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    record_version(conn, migration.version).await
+}
+
+/// Infer the version of a database created before `schema_version` existed
+///
+/// Version 1 only creates missing tables and indices, so unstamped databases report 0
+/// and re-run it to fill in anything an older build never created.
+async fn detect_unversioned(conn: &mut SqliteConnection) -> Result<i64> {
+    if !table_exists(conn, "directory_cache").await? {
+        return Ok(0);
+    }
+
+    let has_stamps = sqlx::query("PRAGMA table_info(directory_cache)")
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "parent_path");
+
+    Ok(if has_stamps { 2 } else { 0 })
+}
+
+async fn table_exists(conn: &mut SqliteConnection, name: &str) -> Result<bool> {
+    let row = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name = ?")
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(row.is_some())
+}
+
+async fn record_version(conn: &mut SqliteConnection, version: i64) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO schema_version (version, applied_at) VALUES (?, ?)")
+        .bind(version)
+        .bind(Utc::now().timestamp())
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
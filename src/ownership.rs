@@ -0,0 +1,104 @@
+use color_eyre::eyre::{eyre, Result};
+use std::path::Path;
+
+/// Which owners and groups a run may delete files for
+///
+/// An empty list places no restriction. When both lists are given a file has to match
+/// both, so `--owner alice --group staff` only deletes alice's files that belong to staff.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OwnerFilter {
+    pub uids: Vec<u32>,
+    pub gids: Vec<u32>,
+}
+
+impl OwnerFilter {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.uids.is_empty() && self.gids.is_empty()
+    }
+
+    /// Whether the file at `path` may be deleted
+    ///
+    /// A file whose owner cannot be read is never allowed through a non-empty filter.
+    #[must_use]
+    pub fn allows(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let Some((uid, gid)) = std::fs::symlink_metadata(path)
+            .ok()
+            .as_ref()
+            .and_then(owner_and_group)
+        else {
+            return false;
+        };
+        (self.uids.is_empty() || self.uids.contains(&uid))
+            && (self.gids.is_empty() || self.gids.contains(&gid))
+    }
+}
+
+#[cfg(unix)]
+fn owner_and_group(metadata: &std::fs::Metadata) -> Option<(u32, u32)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.uid(), metadata.gid()))
+}
+
+#[cfg(not(unix))]
+fn owner_and_group(_metadata: &std::fs::Metadata) -> Option<(u32, u32)> {
+    None
+}
+
+/// The effective user id of this process
+#[cfg(unix)]
+pub fn current_uid() -> Result<u32> {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    Ok(unsafe { libc::geteuid() })
+}
+
+/// Turn a user name or numeric id into a uid
+#[cfg(unix)]
+pub fn resolve_user(user: &str) -> Result<u32> {
+    if let Ok(uid) = user.parse() {
+        return Ok(uid);
+    }
+    let name = std::ffi::CString::new(user).map_err(|_| eyre!("Invalid user name {user:?}"))?;
+    // SAFETY: `name` is a valid C string, and the returned record is read before any
+    // other passwd lookup can overwrite it
+    let entry = unsafe { libc::getpwnam(name.as_ptr()) };
+    if entry.is_null() {
+        return Err(eyre!("No such user: {user}"));
+    }
+    // SAFETY: checked non-null above
+    Ok(unsafe { (*entry).pw_uid })
+}
+
+/// Turn a group name or numeric id into a gid
+#[cfg(unix)]
+pub fn resolve_group(group: &str) -> Result<u32> {
+    if let Ok(gid) = group.parse() {
+        return Ok(gid);
+    }
+    let name = std::ffi::CString::new(group).map_err(|_| eyre!("Invalid group name {group:?}"))?;
+    // SAFETY: as for `getpwnam` in `resolve_user`
+    let entry = unsafe { libc::getgrnam(name.as_ptr()) };
+    if entry.is_null() {
+        return Err(eyre!("No such group: {group}"));
+    }
+    // SAFETY: checked non-null above
+    Ok(unsafe { (*entry).gr_gid })
+}
+
+#[cfg(not(unix))]
+pub fn current_uid() -> Result<u32> {
+    Err(eyre!("Owner filters are only supported on Unix"))
+}
+
+#[cfg(not(unix))]
+pub fn resolve_user(_user: &str) -> Result<u32> {
+    Err(eyre!("Owner filters are only supported on Unix"))
+}
+
+#[cfg(not(unix))]
+pub fn resolve_group(_group: &str) -> Result<u32> {
+    Err(eyre!("Group filters are only supported on Unix"))
+}
@@ -0,0 +1,194 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cache::{DirectoryState, FoundFile, SearchSession, SearchSessionStatus};
+use crate::roots;
+
+/// On-disk formats supported for moving the cache between machines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PortableFormat {
+    /// One JSON object per line, tagged with the table it came from
+    Jsonl,
+    /// A single CSV file with a `table` column and the union of all table columns
+    Csv,
+}
+
+impl PortableFormat {
+    /// Pick a format from a file extension, defaulting to JSON Lines
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => PortableFormat::Csv,
+            _ => PortableFormat::Jsonl,
+        }
+    }
+}
+
+/// A single row from one of the portable cache tables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "table", rename_all = "snake_case")]
+pub enum CacheRecord {
+    DirectoryCache(DirectoryState),
+    SearchSessions(SearchSession),
+    FoundFiles(FoundFile),
+}
+
+/// Flattened CSV row; only the columns belonging to `table` are populated
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CsvRecord {
+    table: String,
+    path: Option<PathBuf>,
+    last_searched_at: Option<i64>,
+    search_completed: Option<bool>,
+    ds_store_found: Option<bool>,
+    ds_store_deleted: Option<bool>,
+    error_message: Option<String>,
+    mtime_ns: Option<i64>,
+    inode: Option<i64>,
+    session_id: Option<String>,
+    root_path: Option<PathBuf>,
+    started_at: Option<i64>,
+    completed_at: Option<i64>,
+    is_recursive: Option<bool>,
+    is_dry_run: Option<bool>,
+    status: Option<SearchSessionStatus>,
+    #[serde(default)]
+    profiles: Option<String>,
+    #[serde(default)]
+    roots: Option<String>,
+    file_path: Option<PathBuf>,
+    discovered_at: Option<i64>,
+    size: Option<i64>,
+}
+
+impl From<&CacheRecord> for CsvRecord {
+    fn from(record: &CacheRecord) -> Self {
+        match record {
+            CacheRecord::DirectoryCache(state) => CsvRecord {
+                table: "directory_cache".to_string(),
+                path: Some(state.path.clone()),
+                last_searched_at: Some(state.last_searched_at),
+                search_completed: Some(state.search_completed),
+                ds_store_found: Some(state.ds_store_found),
+                ds_store_deleted: Some(state.ds_store_deleted),
+                error_message: state.error_message.clone(),
+                mtime_ns: state.mtime_ns,
+                inode: state.inode,
+                ..Default::default()
+            },
+            CacheRecord::SearchSessions(session) => CsvRecord {
+                table: "search_sessions".to_string(),
+                session_id: Some(session.session_id.clone()),
+                root_path: Some(session.root_path.clone()),
+                started_at: Some(session.started_at),
+                completed_at: session.completed_at,
+                is_recursive: Some(session.is_recursive),
+                is_dry_run: Some(session.is_dry_run),
+                status: Some(session.status.clone()),
+                profiles: Some(session.profiles.clone()),
+                roots: Some(roots::session_key(&session.roots)),
+                ..Default::default()
+            },
+            CacheRecord::FoundFiles(file) => CsvRecord {
+                table: "found_files".to_string(),
+                session_id: Some(file.session_id.clone()),
+                file_path: Some(file.file_path.clone()),
+                discovered_at: Some(file.discovered_at),
+                size: file.size,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl TryFrom<CsvRecord> for CacheRecord {
+    type Error = color_eyre::eyre::Report;
+
+    fn try_from(row: CsvRecord) -> Result<Self> {
+        fn required<T>(value: Option<T>, table: &str, column: &str) -> Result<T> {
+            value.ok_or_else(|| eyre!("{table} row is missing the `{column}` column"))
+        }
+
+        let table = row.table.as_str();
+        match table {
+            "directory_cache" => Ok(CacheRecord::DirectoryCache(DirectoryState {
+                path: required(row.path, table, "path")?,
+                last_searched_at: required(row.last_searched_at, table, "last_searched_at")?,
+                search_completed: required(row.search_completed, table, "search_completed")?,
+                ds_store_found: row.ds_store_found.unwrap_or(false),
+                ds_store_deleted: row.ds_store_deleted.unwrap_or(false),
+                error_message: row.error_message,
+                mtime_ns: row.mtime_ns,
+                inode: row.inode,
+            })),
+            "search_sessions" => Ok(CacheRecord::SearchSessions(SearchSession {
+                session_id: required(row.session_id, table, "session_id")?,
+                root_path: required(row.root_path, table, "root_path")?,
+                started_at: required(row.started_at, table, "started_at")?,
+                completed_at: row.completed_at,
+                is_recursive: required(row.is_recursive, table, "is_recursive")?,
+                is_dry_run: required(row.is_dry_run, table, "is_dry_run")?,
+                status: required(row.status, table, "status")?,
+                profiles: row.profiles.unwrap_or_default(),
+                roots: roots::from_session_key(&row.roots.unwrap_or_default()),
+            })),
+            "found_files" => Ok(CacheRecord::FoundFiles(FoundFile {
+                session_id: required(row.session_id, table, "session_id")?,
+                file_path: required(row.file_path, table, "file_path")?,
+                discovered_at: required(row.discovered_at, table, "discovered_at")?,
+                size: row.size,
+            })),
+            other => Err(eyre!("Unknown cache table in import: {other}")),
+        }
+    }
+}
+
+/// Serialize cache records to `writer` in the requested format
+pub fn write_records<W: Write>(
+    mut writer: W,
+    format: PortableFormat,
+    records: &[CacheRecord],
+) -> Result<()> {
+    match format {
+        PortableFormat::Jsonl => {
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        PortableFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            for record in records {
+                csv_writer.serialize(CsvRecord::from(record))?;
+            }
+            csv_writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse cache records from `reader` in the requested format
+pub fn read_records<R: Read>(reader: R, format: PortableFormat) -> Result<Vec<CacheRecord>> {
+    match format {
+        PortableFormat::Jsonl => {
+            let mut records = Vec::new();
+            for (line_number, line) in BufReader::new(reader).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(&line)
+                    .map_err(|e| eyre!("Invalid record on line {}: {e}", line_number + 1))?;
+                records.push(record);
+            }
+            Ok(records)
+        }
+        PortableFormat::Csv => csv::Reader::from_reader(reader)
+            .deserialize::<CsvRecord>()
+            .map(|row| CacheRecord::try_from(row?))
+            .collect(),
+    }
+}
@@ -0,0 +1,88 @@
+use color_eyre::eyre::{eyre, Result};
+use glob::Pattern;
+use std::collections::BTreeMap;
+
+/// Profiles that come with `dds`; a profile of the same name in the config replaces one
+pub const BUILT_IN: [(&str, &[&str]); 3] = [
+    ("mac", &[".DS_Store", "._*", "Icon\r", ".localized"]),
+    ("windows", &["Thumbs.db", "desktop.ini", "ehthumbs.db"]),
+    ("editor", &["*.swp", "*~"]),
+];
+
+/// The file name patterns a run deletes, from the profiles selected with `--profile`
+///
+/// With no profile selected only `.DS_Store` files match, as they always have.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileSet {
+    names: Vec<String>,
+    patterns: Vec<Pattern>,
+}
+
+impl ProfileSet {
+    /// Look up each of `names` among the user's profiles, then the built-in ones
+    pub fn resolve(names: &[String], custom: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        let mut selected: Vec<String> = names.to_vec();
+        selected.sort();
+        selected.dedup();
+
+        let mut patterns = Vec::new();
+        for name in &selected {
+            let globs: Vec<String> = match custom.get(name) {
+                Some(globs) => globs.clone(),
+                None => BUILT_IN
+                    .iter()
+                    .find(|(built_in, _)| built_in == name)
+                    .map(|(_, globs)| globs.iter().map(|glob| glob.to_string()).collect())
+                    .ok_or_else(|| {
+                        eyre!(
+                            "Unknown profile `{name}`; the built-in ones are mac, windows and editor"
+                        )
+                    })?,
+            };
+            for glob in globs {
+                patterns.push(
+                    Pattern::new(&glob)
+                        .map_err(|e| eyre!("Invalid pattern {glob:?} in profile `{name}`: {e}"))?,
+                );
+            }
+        }
+
+        Ok(Self {
+            names: selected,
+            patterns,
+        })
+    }
+
+    /// Whether this is the plain `.DS_Store`-only selection
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The selected profile names, sorted and joined, as stored with a search session
+    #[must_use]
+    pub fn key(&self) -> String {
+        self.names.join(",")
+    }
+
+    /// What the files this selection deletes are called in messages
+    #[must_use]
+    pub fn files(&self) -> &'static str {
+        if self.is_default() {
+            ".DS_Store files"
+        } else {
+            "files"
+        }
+    }
+
+    /// Whether a file with this name is to be deleted
+    #[must_use]
+    pub fn matches(&self, file_name: &str) -> bool {
+        if self.is_default() {
+            return file_name == ".DS_Store";
+        }
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(file_name))
+    }
+}
@@ -0,0 +1,698 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+use color_eyre::eyre::{eyre, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::cache::{
+    owner_uid, Cache, DeletionOutcome, DeletionQuery, DeletionRecord, DirectoryState, FoundFile,
+    Hit, SearchSession,
+};
+use crate::html;
+use crate::RunSummary;
+
+/// Granularity of the buckets in a trend report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TrendPeriod {
+    Day,
+    /// ISO weeks, starting on Monday
+    #[default]
+    Week,
+}
+
+impl TrendPeriod {
+    /// First day of the period containing the Unix timestamp `at`, in UTC
+    #[must_use]
+    pub fn start_of(self, at: i64) -> NaiveDate {
+        let day = DateTime::from_timestamp(at, 0)
+            .map(|at| at.date_naive())
+            .unwrap_or_default();
+        match self {
+            TrendPeriod::Day => day,
+            TrendPeriod::Week => {
+                day - chrono::Duration::days(i64::from(day.weekday().num_days_from_monday()))
+            }
+        }
+    }
+}
+
+/// Hits found and deleted under one search root during one period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrendBucket {
+    pub root: PathBuf,
+    pub period_start: NaiveDate,
+    /// Distinct `.DS_Store` files discovered during the period
+    pub found: u64,
+    pub deleted: u64,
+}
+
+/// A directory that grew a new `.DS_Store` after being cleaned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReinfectedDirectory {
+    pub path: PathBuf,
+    pub reinfections: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrendReport {
+    /// Ordered by root, then period
+    pub buckets: Vec<TrendBucket>,
+    /// Most re-infected first
+    pub reinfected: Vec<ReinfectedDirectory>,
+    /// Mean seconds between a directory being cleaned and its next `.DS_Store` turning up
+    pub mean_reinfection_secs: Option<f64>,
+    pub reinfection_count: u64,
+}
+
+/// Label used for deletions whose session has since been removed from the cache
+const UNKNOWN_ROOT: &str = "<unknown>";
+
+/// Build a trend report from the session history and audit log in `cache`
+///
+/// When `root` is given, only hits at or below it are counted.
+pub async fn trends(
+    cache: &Cache,
+    root: Option<&Path>,
+    period: TrendPeriod,
+    top: usize,
+) -> Result<TrendReport> {
+    let sessions = cache.get_sessions().await?;
+    let mut found = cache.get_found_history().await?;
+    let mut deletions = cache.get_deletions(&DeletionQuery::default()).await?;
+
+    if let Some(root) = root {
+        found.retain(|file| file.file_path.starts_with(root));
+        deletions.retain(|record| record.file_path.starts_with(root));
+    }
+
+    Ok(build_trends(&sessions, &found, &deletions, period, top))
+}
+
+/// Aggregate raw history into per-period counts and re-infection statistics
+///
+/// A directory counts as re-infected when a `.DS_Store` is discovered there after one
+/// was successfully deleted. Only the first discovery after each deletion is counted,
+/// so a file seen by several later dry runs is a single re-infection.
+#[must_use]
+pub fn build_trends(
+    sessions: &[SearchSession],
+    found: &[FoundFile],
+    deletions: &[DeletionRecord],
+    period: TrendPeriod,
+    top: usize,
+) -> TrendReport {
+    let roots: HashMap<&str, &Path> = sessions
+        .iter()
+        .map(|session| (session.session_id.as_str(), session.root_path.as_path()))
+        .collect();
+    let root_of = |session_id: Option<&str>| -> PathBuf {
+        session_id
+            .and_then(|id| roots.get(id))
+            .map_or_else(|| PathBuf::from(UNKNOWN_ROOT), |root| root.to_path_buf())
+    };
+
+    // (root, period) -> (distinct files found, deletions)
+    let mut counts: BTreeMap<(PathBuf, NaiveDate), (HashSet<&Path>, u64)> = BTreeMap::new();
+    for file in found {
+        let key = (
+            root_of(Some(&file.session_id)),
+            period.start_of(file.discovered_at),
+        );
+        counts
+            .entry(key)
+            .or_default()
+            .0
+            .insert(file.file_path.as_path());
+    }
+
+    let deleted: Vec<&DeletionRecord> = deletions
+        .iter()
+        .filter(|record| record.outcome == DeletionOutcome::Deleted)
+        .collect();
+    for record in &deleted {
+        let key = (
+            root_of(record.session_id.as_deref()),
+            period.start_of(record.attempted_at),
+        );
+        counts.entry(key).or_default().1 += 1;
+    }
+
+    let buckets = counts
+        .into_iter()
+        .map(|((root, period_start), (files, deleted))| TrendBucket {
+            root,
+            period_start,
+            found: files.len() as u64,
+            deleted,
+        })
+        .collect();
+
+    // Every time each file was seen, sorted so the first sighting after a deletion is a binary search
+    let mut sightings: HashMap<&Path, Vec<i64>> = HashMap::new();
+    for file in found {
+        sightings
+            .entry(file.file_path.as_path())
+            .or_default()
+            .push(file.discovered_at);
+    }
+    for times in sightings.values_mut() {
+        times.sort_unstable();
+    }
+
+    let mut per_directory: HashMap<PathBuf, u64> = HashMap::new();
+    let mut total_secs = 0_i64;
+    let mut reinfection_count = 0_u64;
+    for record in &deleted {
+        let Some(times) = sightings.get(record.file_path.as_path()) else {
+            continue;
+        };
+        let next = times.partition_point(|&at| at <= record.attempted_at);
+        if let Some(&seen_again) = times.get(next) {
+            total_secs += seen_again - record.attempted_at;
+            reinfection_count += 1;
+            let directory = record
+                .file_path
+                .parent()
+                .unwrap_or(&record.file_path)
+                .to_path_buf();
+            *per_directory.entry(directory).or_default() += 1;
+        }
+    }
+
+    let mut reinfected: Vec<ReinfectedDirectory> = per_directory
+        .into_iter()
+        .map(|(path, reinfections)| ReinfectedDirectory { path, reinfections })
+        .collect();
+    reinfected.sort_by(|a, b| {
+        b.reinfections
+            .cmp(&a.reinfections)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    reinfected.truncate(top);
+
+    TrendReport {
+        buckets,
+        reinfected,
+        mean_reinfection_secs: (reinfection_count > 0)
+            .then(|| total_secs as f64 / reinfection_count as f64),
+        reinfection_count,
+    }
+}
+
+/// Hits and their combined size beneath one top-level directory of the search root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageEntry {
+    /// The top-level directory, or the search root itself for hits directly inside it
+    pub path: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// How much space the hits of one run take up, like `du` for `.DS_Store` files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageReport {
+    pub total_files: u64,
+    pub total_bytes: u64,
+    /// Files whose size could not be read, which are left out of every byte count
+    pub unsized_files: u64,
+    pub reclaimed_bytes: u64,
+    /// Biggest hits first
+    pub largest: Vec<Hit>,
+    /// Biggest subtotal first
+    pub subtotals: Vec<UsageEntry>,
+}
+
+/// Summarise the disk usage of `hits`, grouped by the top-level directory under `root`
+#[must_use]
+pub fn usage(root: &Path, hits: &[Hit], reclaimed_bytes: u64, top: usize) -> UsageReport {
+    let mut subtotals: HashMap<PathBuf, (u64, u64)> = HashMap::new();
+    let mut report = UsageReport {
+        reclaimed_bytes,
+        ..Default::default()
+    };
+
+    for hit in hits {
+        let bytes = hit.size.unwrap_or(0);
+        report.total_files += 1;
+        report.total_bytes += bytes;
+        if hit.size.is_none() {
+            report.unsized_files += 1;
+        }
+
+        // Hits outside the root (or directly inside it) are counted against the root
+        let group = hit
+            .path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .and_then(|relative| relative.components().next())
+            .map_or_else(|| root.to_path_buf(), |first| root.join(first));
+        let subtotal = subtotals.entry(group).or_default();
+        subtotal.0 += 1;
+        subtotal.1 += bytes;
+    }
+
+    let mut largest: Vec<Hit> = hits
+        .iter()
+        .filter(|hit| hit.size.is_some())
+        .cloned()
+        .collect();
+    largest.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    largest.truncate(top);
+    report.largest = largest;
+
+    report.subtotals = subtotals
+        .into_iter()
+        .map(|(path, (files, bytes))| UsageEntry { path, files, bytes })
+        .collect();
+    report
+        .subtotals
+        .sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+
+    report
+}
+
+/// One directory in a [`hit_tree`], with the hits found anywhere beneath it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HitTree {
+    pub path: PathBuf,
+    /// Hits in this directory and all of its descendants
+    pub hits: u64,
+    /// Largest subtree first
+    pub children: Vec<HitTree>,
+    /// Child directories left out because they fell below the threshold
+    pub pruned_dirs: u64,
+    /// Hits beneath the pruned child directories
+    pub pruned_hits: u64,
+}
+
+/// Intermediate tree keyed by path component, before sorting and pruning
+#[derive(Default)]
+struct TreeCounts {
+    hits: u64,
+    children: BTreeMap<OsString, TreeCounts>,
+}
+
+/// Collapse a flat hit list into a directory tree with a hit count per subtree
+///
+/// Directories deeper than `max_depth` below `root` are folded into their ancestor,
+/// and child directories with fewer than `min_hits` hits are summarised instead of
+/// listed. Hits outside `root` are counted against `root` itself.
+#[must_use]
+pub fn hit_tree<'a>(
+    root: &Path,
+    hits: impl IntoIterator<Item = &'a Path>,
+    max_depth: Option<usize>,
+    min_hits: u64,
+) -> HitTree {
+    let mut counts = TreeCounts::default();
+    for hit in hits {
+        counts.hits += 1;
+        let Some(relative) = hit
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+        else {
+            continue;
+        };
+
+        let mut node = &mut counts;
+        for (depth, component) in relative.components().enumerate() {
+            if max_depth.is_some_and(|max| depth >= max) {
+                break;
+            }
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            node = node.children.entry(name.to_os_string()).or_default();
+            node.hits += 1;
+        }
+    }
+
+    prune(root.to_path_buf(), counts, min_hits)
+}
+
+fn prune(path: PathBuf, counts: TreeCounts, min_hits: u64) -> HitTree {
+    let mut tree = HitTree {
+        path,
+        hits: counts.hits,
+        children: Vec::new(),
+        pruned_dirs: 0,
+        pruned_hits: 0,
+    };
+
+    for (name, child) in counts.children {
+        if child.hits < min_hits {
+            tree.pruned_dirs += 1;
+            tree.pruned_hits += child.hits;
+        } else {
+            let child_path = tree.path.join(name);
+            tree.children.push(prune(child_path, child, min_hits));
+        }
+    }
+    tree.children
+        .sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.path.cmp(&b.path)));
+
+    tree
+}
+
+/// Draw a [`HitTree`] with box-drawing characters, one directory per line
+#[must_use]
+pub fn render_tree(tree: &HitTree) -> String {
+    fn draw(out: &mut String, tree: &HitTree, prefix: &str) {
+        let pruned = (tree.pruned_dirs > 0).then(|| {
+            format!(
+                "… {} smaller {} ({})",
+                tree.pruned_dirs,
+                if tree.pruned_dirs == 1 {
+                    "directory"
+                } else {
+                    "directories"
+                },
+                tree.pruned_hits
+            )
+        });
+        let lines = tree.children.len() + usize::from(pruned.is_some());
+
+        for (index, child) in tree.children.iter().enumerate() {
+            let last = index + 1 == lines;
+            let name = child.path.file_name().map_or_else(
+                || child.path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            );
+            let _ = writeln!(
+                out,
+                "{prefix}{}{name} ({})",
+                if last { "└── " } else { "├── " },
+                child.hits
+            );
+            draw(
+                out,
+                child,
+                &format!("{prefix}{}", if last { "    " } else { "│   " }),
+            );
+        }
+        if let Some(pruned) = pruned {
+            let _ = writeln!(out, "{prefix}└── {pruned}");
+        }
+    }
+
+    let mut out = format!("{} ({})\n", tree.path.display(), tree.hits);
+    draw(&mut out, tree, "");
+    out
+}
+
+/// Render a byte count with binary units, as `du -h` does
+#[must_use]
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Output formats for a session report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// A self-contained HTML page for people who do not live in a terminal
+    #[default]
+    Html,
+    /// One row per hit, for spreadsheets
+    Csv,
+}
+
+impl ReportFormat {
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// Everything known about one search session, shared by every report format
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub session: SearchSession,
+    pub summary: RunSummary,
+    /// Attempts made by this session, newest first
+    pub deletions: Vec<DeletionRecord>,
+    /// Directories this session could not search
+    pub errors: Vec<DirectoryState>,
+}
+
+/// Gather everything the session reports show about one session from the cache
+///
+/// `live` carries the counters of the run that just finished. For a stored session the
+/// summary is rebuilt from what the cache kept, which cannot tell freshly searched
+/// directories from resumed or skipped ones.
+pub async fn session_report(
+    cache: &Cache,
+    session_id: &str,
+    live: Option<&RunSummary>,
+) -> Result<SessionReport> {
+    let session = cache
+        .get_sessions()
+        .await?
+        .into_iter()
+        .find(|session| session.session_id == session_id)
+        .ok_or_else(|| eyre!("No search session with id {session_id}"))?;
+
+    let deletions = cache
+        .get_deletions(&DeletionQuery {
+            session_id: Some(session_id.to_string()),
+            ..Default::default()
+        })
+        .await?;
+
+    // The cache only keeps each directory's latest state, so keep the errors from this session's run
+    let mut errors = cache.get_error_directories(&session.root_path).await?;
+    errors.retain(|state| {
+        state.last_searched_at >= session.started_at
+            && session
+                .completed_at
+                .map_or(true, |completed_at| state.last_searched_at <= completed_at)
+    });
+
+    let summary = match live {
+        Some(summary) => summary.clone(),
+        None => {
+            let hits = cache.load_hits(session_id).await?;
+            let mut summary = RunSummary {
+                root: session.root_path.clone(),
+                session_id: session.session_id.clone(),
+                dry_run: session.is_dry_run,
+                directories_searched: cache.get_session_searched_count(session_id).await?,
+                directories_errored: errors.len(),
+                hits_found: hits.len(),
+                bytes_found: hits.iter().filter_map(|hit| hit.size).sum(),
+                hits,
+                duration: Duration::from_secs(
+                    session
+                        .completed_at
+                        .and_then(|completed_at| {
+                            u64::try_from(completed_at - session.started_at).ok()
+                        })
+                        .unwrap_or(0),
+                ),
+                ..Default::default()
+            };
+            for record in &deletions {
+                match record.outcome {
+                    DeletionOutcome::Deleted => {
+                        summary.deleted += 1;
+                        summary.bytes_reclaimed += record
+                            .size
+                            .and_then(|size| u64::try_from(size).ok())
+                            .unwrap_or(0);
+                    }
+                    DeletionOutcome::Missing => summary.missing += 1,
+                    DeletionOutcome::Failed => summary.failed += 1,
+                    DeletionOutcome::Rewritten => summary.archives_cleaned += 1,
+                }
+            }
+            summary
+        }
+    };
+
+    Ok(SessionReport {
+        session,
+        summary,
+        deletions,
+        errors,
+    })
+}
+
+/// What happened to a hit by the end of its session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HitAction {
+    /// Found but not deleted, as in a dry run
+    Found,
+    Deleted,
+    /// Already gone by the time it was deleted
+    Missing,
+    Failed,
+    /// An archive rewritten without its macOS entries
+    Rewritten,
+}
+
+/// One line of the CSV report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HitRow {
+    pub path: PathBuf,
+    pub parent: PathBuf,
+    pub size: Option<u64>,
+    /// Modification time, formatted in UTC
+    pub mtime: Option<String>,
+    /// Numeric user id of the file's owner
+    pub owner: Option<i64>,
+    pub action: HitAction,
+    pub error: Option<String>,
+}
+
+/// One row per hit, combining what the walk found with what the deletion attempt recorded
+///
+/// Archives with macOS entries get a row each after the hits. Hits that were never
+/// deleted are looked up on disk for their modification time and owner.
+#[must_use]
+pub fn hit_rows(report: &SessionReport) -> Vec<HitRow> {
+    let attempts: HashMap<&Path, &DeletionRecord> = report
+        .deletions
+        .iter()
+        .map(|record| (record.file_path.as_path(), record))
+        .collect();
+    let archives: Vec<Hit> = report
+        .summary
+        .archives
+        .iter()
+        .map(|archive| Hit::of(&archive.path))
+        .collect();
+
+    report
+        .summary
+        .hits
+        .iter()
+        .chain(&archives)
+        .map(|hit| {
+            let parent = hit.path.parent().unwrap_or(&hit.path).to_path_buf();
+            match attempts.get(hit.path.as_path()) {
+                Some(record) => HitRow {
+                    path: hit.path.clone(),
+                    parent,
+                    size: hit
+                        .size
+                        .or_else(|| record.size.and_then(|size| u64::try_from(size).ok())),
+                    mtime: record.mtime.map(timestamp),
+                    owner: record.owner_uid,
+                    action: match record.outcome {
+                        DeletionOutcome::Deleted => HitAction::Deleted,
+                        DeletionOutcome::Missing => HitAction::Missing,
+                        DeletionOutcome::Failed => HitAction::Failed,
+                        DeletionOutcome::Rewritten => HitAction::Rewritten,
+                    },
+                    error: record.error_message.clone(),
+                },
+                None => {
+                    let metadata = std::fs::symlink_metadata(&hit.path).ok();
+                    HitRow {
+                        path: hit.path.clone(),
+                        parent,
+                        size: hit.size,
+                        mtime: metadata
+                            .as_ref()
+                            .and_then(|metadata| metadata.modified().ok())
+                            .and_then(|modified| {
+                                modified.duration_since(std::time::UNIX_EPOCH).ok()
+                            })
+                            .and_then(|elapsed| i64::try_from(elapsed.as_secs()).ok())
+                            .map(timestamp),
+                        owner: metadata.as_ref().and_then(owner_uid),
+                        action: HitAction::Found,
+                        error: None,
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Write the hits of a session as CSV, one row per hit
+pub fn write_csv<W: Write>(writer: W, report: &SessionReport) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for row in hit_rows(report) {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Render `report` in `format` and write it to `path`, creating its parent directory if needed
+pub fn write_session_report(
+    path: &Path,
+    format: ReportFormat,
+    report: &SessionReport,
+) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::io::BufWriter::new(
+        std::fs::File::create(path)
+            .map_err(|e| eyre!("Could not write report to {}: {e}", path.display()))?,
+    );
+    match format {
+        ReportFormat::Html => {
+            file.write_all(html::render(report, chrono::Utc::now().timestamp()).as_bytes())?;
+        }
+        ReportFormat::Csv => write_csv(&mut file, report)?,
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Default file name for a session's report, e.g. `dds-report-<session id>.csv`
+#[must_use]
+pub fn default_file_name(session_id: &str, format: ReportFormat) -> PathBuf {
+    PathBuf::from(format!("dds-report-{session_id}.{}", format.extension()))
+}
+
+/// Format a Unix timestamp for people, in UTC
+#[must_use]
+pub fn timestamp(at: i64) -> String {
+    DateTime::from_timestamp(at, 0).map_or_else(
+        || at.to_string(),
+        |at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    )
+}
+
+/// Render a duration in seconds using the largest sensible unit
+#[must_use]
+pub fn format_duration(secs: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = 60.0 * MINUTE;
+    const DAY: f64 = 24.0 * HOUR;
+
+    if secs >= DAY {
+        format!("{:.1} days", secs / DAY)
+    } else if secs >= HOUR {
+        format!("{:.1} hours", secs / HOUR)
+    } else if secs >= MINUTE {
+        format!("{:.1} minutes", secs / MINUTE)
+    } else {
+        format!("{secs:.0} seconds")
+    }
+}
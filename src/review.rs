@@ -0,0 +1,214 @@
+use color_eyre::eyre::{eyre, Result};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::cache::Hit;
+
+/// How many directories the overview lists before summarising the rest
+const OVERVIEW_LIMIT: usize = 20;
+
+/// The hits approved for deletion and the ones the user chose to keep
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Review {
+    pub approved: Vec<Hit>,
+    pub rejected: Vec<Hit>,
+}
+
+/// Directories holding hits, nested the way they are on disk
+#[derive(Default)]
+struct ReviewNode {
+    path: PathBuf,
+    files: Vec<Hit>,
+    children: BTreeMap<OsString, ReviewNode>,
+}
+
+impl ReviewNode {
+    /// Hits outside `root` are treated as if they were directly inside it
+    fn build(root: &Path, hits: Vec<Hit>) -> Self {
+        let mut tree = ReviewNode {
+            path: root.to_path_buf(),
+            ..Default::default()
+        };
+        for hit in hits {
+            let relative = hit
+                .path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(root).ok())
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+
+            let mut node = &mut tree;
+            for component in relative.components() {
+                let Component::Normal(name) = component else {
+                    continue;
+                };
+                let path = node.path.join(name);
+                node = node
+                    .children
+                    .entry(name.to_os_string())
+                    .or_insert_with(|| ReviewNode {
+                        path,
+                        ..Default::default()
+                    });
+            }
+            node.files.push(hit);
+        }
+        tree
+    }
+
+    fn count(&self) -> usize {
+        self.files.len() + self.children.values().map(ReviewNode::count).sum::<usize>()
+    }
+
+    fn into_hits(self, out: &mut Vec<Hit>) {
+        out.extend(self.files);
+        for child in self.children.into_values() {
+            child.into_hits(out);
+        }
+    }
+
+    /// Directories that hold hits directly, with how many, in path order
+    fn directories(&self, out: &mut Vec<(PathBuf, usize)>) {
+        if !self.files.is_empty() {
+            out.push((self.path.clone(), self.files.len()));
+        }
+        for child in self.children.values() {
+            child.directories(out);
+        }
+    }
+}
+
+/// Reads answers and writes prompts for a review
+struct Prompter<'a, R, W> {
+    input: &'a mut R,
+    output: &'a mut W,
+}
+
+impl<R: BufRead, W: Write> Prompter<'_, R, W> {
+    /// Ask `question` until one of `choices` is given, returning its index
+    fn ask(&mut self, question: &str, choices: &[char]) -> Result<usize> {
+        loop {
+            write!(self.output, "{question} ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(eyre!(
+                    "Review ended before every file was decided; nothing was deleted"
+                ));
+            }
+            let answer = line.trim().to_lowercase();
+            if let Some(index) = answer
+                .chars()
+                .next()
+                .and_then(|first| choices.iter().position(|&choice| choice == first))
+            {
+                return Ok(index);
+            }
+
+            let listed: Vec<String> = choices.iter().map(char::to_string).collect();
+            writeln!(self.output, "Please answer {}.", listed.join(", "))?;
+        }
+    }
+
+    fn file(&mut self, hit: Hit, review: &mut Review) -> Result<()> {
+        let question = format!("{}: delete [y] or keep [n]?", hit.path.display());
+        match self.ask(&question, &['y', 'n'])? {
+            0 => review.approved.push(hit),
+            _ => review.rejected.push(hit),
+        }
+        Ok(())
+    }
+
+    fn subtree(&mut self, mut node: ReviewNode, review: &mut Review) -> Result<()> {
+        // Skip straight through directories that only lead to one other directory
+        while node.files.is_empty() && node.children.len() == 1 {
+            node = node
+                .children
+                .into_values()
+                .next()
+                .expect("Directory has exactly one child");
+        }
+
+        let count = node.count();
+        if count == 1 {
+            let mut hits = Vec::with_capacity(1);
+            node.into_hits(&mut hits);
+            return self.file(hits.remove(0), review);
+        }
+
+        let question = format!(
+            "{} ({count} files): delete all [y], keep all [n], or go through it [d]?",
+            node.path.display()
+        );
+        match self.ask(&question, &['y', 'n', 'd'])? {
+            0 => node.into_hits(&mut review.approved),
+            1 => node.into_hits(&mut review.rejected),
+            _ => self.contents(node, review)?,
+        }
+        Ok(())
+    }
+
+    fn contents(&mut self, node: ReviewNode, review: &mut Review) -> Result<()> {
+        for hit in node.files {
+            self.file(hit, review)?;
+        }
+        for child in node.children.into_values() {
+            self.subtree(child, review)?;
+        }
+        Ok(())
+    }
+}
+
+/// Show the hits grouped by directory and ask which of them to delete
+///
+/// The user can approve or reject everything at once, or walk the directory tree and
+/// decide per subtree and per file. If the input ends before every hit has been
+/// decided, an error is returned so the caller deletes nothing.
+pub fn review<R: BufRead, W: Write>(
+    root: &Path,
+    hits: Vec<Hit>,
+    input: &mut R,
+    output: &mut W,
+) -> Result<Review> {
+    let mut review = Review::default();
+    if hits.is_empty() {
+        return Ok(review);
+    }
+
+    let tree = ReviewNode::build(root, hits);
+    let mut directories = Vec::new();
+    tree.directories(&mut directories);
+
+    writeln!(
+        output,
+        "\n{} .DS_Store files in {} directories under {}:",
+        tree.count(),
+        directories.len(),
+        root.display()
+    )?;
+    for (path, count) in directories.iter().take(OVERVIEW_LIMIT) {
+        writeln!(output, "  {count:>6}  {}", path.display())?;
+    }
+    if directories.len() > OVERVIEW_LIMIT {
+        writeln!(
+            output,
+            "  … and {} more directories",
+            directories.len() - OVERVIEW_LIMIT
+        )?;
+    }
+
+    let mut prompter = Prompter { input, output };
+    match prompter.ask(
+        "Delete all [a], keep all [r], or decide by directory [d]?",
+        &['a', 'r', 'd'],
+    )? {
+        0 => tree.into_hits(&mut review.approved),
+        1 => tree.into_hits(&mut review.rejected),
+        _ => prompter.contents(tree, &mut review)?,
+    }
+
+    Ok(review)
+}
@@ -0,0 +1,83 @@
+use color_eyre::eyre::Result;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// Search directories listed one per line, as given to `--roots-from`
+///
+/// Blank lines and lines starting with `#` are skipped; surrounding whitespace is not
+/// part of the path.
+pub fn read_roots<R: BufRead>(input: R) -> Result<Vec<String>> {
+    let mut roots = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        roots.push(line.to_string());
+    }
+    Ok(roots)
+}
+
+/// Drop repeated roots and, for a recursive search, roots inside another root, keeping
+/// the rest in sorted order
+///
+/// A recursive search would only walk a nested root twice, so the outer one covers it.
+#[must_use]
+pub fn dedupe(mut roots: Vec<PathBuf>, recursive: bool) -> Vec<PathBuf> {
+    roots.sort();
+    roots.dedup();
+    if !recursive {
+        return roots;
+    }
+    let mut kept: Vec<PathBuf> = Vec::with_capacity(roots.len());
+    for root in roots {
+        // Sorting puts every root right after any root it is inside
+        if kept.last().is_some_and(|outer| root.starts_with(outer)) {
+            continue;
+        }
+        kept.push(root);
+    }
+    kept
+}
+
+/// The deepest directory holding every root, which a session is filed under
+#[must_use]
+pub fn common_ancestor(roots: &[PathBuf]) -> PathBuf {
+    let Some((first, rest)) = roots.split_first() else {
+        return PathBuf::new();
+    };
+    let mut ancestor: &Path = first;
+    for root in rest {
+        while !root.starts_with(ancestor) {
+            match ancestor.parent() {
+                Some(parent) => ancestor = parent,
+                None => return PathBuf::new(),
+            }
+        }
+    }
+    ancestor.to_path_buf()
+}
+
+/// The roots as a search session stores them: empty when there is only one, since the
+/// session's root path is then that root, and one per line otherwise
+#[must_use]
+pub fn session_key(roots: &[PathBuf]) -> String {
+    if roots.len() < 2 {
+        return String::new();
+    }
+    roots
+        .iter()
+        .map(|root| root.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The roots stored with a search session, the reverse of [`session_key`]
+#[must_use]
+pub fn from_session_key(key: &str) -> Vec<PathBuf> {
+    key.lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
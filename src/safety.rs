@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+/// Why deleting under `root` needs explicit confirmation, if it does
+///
+/// Both paths are compared after resolving symlinks, so `/home/me/.` and a symlink to
+/// the home directory are caught as well.
+#[must_use]
+pub fn broad_root_reason(root: &Path, home: Option<&Path>) -> Option<&'static str> {
+    let root = canonical(root);
+    if root.parent().is_none() {
+        return Some("the root of the filesystem");
+    }
+    if home.is_some_and(|home| canonical(home) == root) {
+        return Some("your home directory");
+    }
+    None
+}
+
+/// Whether `path` is one of `protected` or lies beneath one of them
+///
+/// The path is checked both as given and with symlinks in its directory resolved, so a
+/// protected directory reached through a symlinked ancestor is still protected.
+#[must_use]
+pub fn is_protected(path: &Path, protected: &[PathBuf]) -> bool {
+    let resolved = canonical_parent(path);
+    protected
+        .iter()
+        .any(|protected| path.starts_with(protected) || resolved.starts_with(protected))
+}
+
+/// Whether removing the whole of `dir` would touch a protected path
+#[must_use]
+pub fn holds_protected(dir: &Path, protected: &[PathBuf]) -> bool {
+    let resolved = canonical(dir);
+    is_protected(dir, protected)
+        || protected
+            .iter()
+            .any(|protected| protected.starts_with(dir) || protected.starts_with(&resolved))
+}
+
+/// Configured paths both as written and with symlinks resolved, so they match however
+/// the walker reaches them
+///
+/// Paths that do not exist yet are kept as written.
+#[must_use]
+pub fn resolve_protected(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut resolved = Vec::with_capacity(paths.len() * 2);
+    for path in paths {
+        resolved.push(path.clone());
+        let canonical = canonical(path);
+        if canonical != *path {
+            resolved.push(canonical);
+        }
+    }
+    resolved
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `path` with symlinks in its parent directory resolved; the file itself may be gone
+fn canonical_parent(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => canonical(parent).join(name),
+        _ => canonical(path),
+    }
+}
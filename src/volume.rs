@@ -0,0 +1,113 @@
+use color_eyre::eyre::Result;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::report;
+
+/// Directories macOS leaves at the root of every volume it mounts
+pub const METADATA_DIRS: [&str; 4] = [
+    ".Spotlight-V100",
+    ".fseventsd",
+    ".Trashes",
+    ".TemporaryItems",
+];
+
+/// Whether and how a run removes macOS metadata directories from the volume root
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VolumeCleanup {
+    #[default]
+    Off,
+    /// List the directories and ask before removing them
+    Ask,
+    /// Remove the directories without asking
+    Confirmed,
+}
+
+impl VolumeCleanup {
+    #[must_use]
+    pub fn is_enabled(self) -> bool {
+        self != Self::Off
+    }
+}
+
+/// A metadata directory at the volume root, with what it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataDir {
+    pub path: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// The metadata directories directly inside `root`
+///
+/// Symlinks by those names are left out, so nothing outside the volume is ever
+/// counted or removed.
+#[must_use]
+pub fn find_metadata_dirs(root: &Path) -> Vec<MetadataDir> {
+    METADATA_DIRS
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir()))
+        .map(|path| {
+            let (files, bytes) = tally(&path);
+            MetadataDir { path, files, bytes }
+        })
+        .collect()
+}
+
+/// Files and bytes below `dir`, not following symlinks and skipping what cannot be read
+fn tally(dir: &Path) -> (u64, u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    let mut totals = (0, 0);
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            let (files, bytes) = tally(&entry.path());
+            totals.0 += files;
+            totals.1 += bytes;
+        } else {
+            totals.0 += 1;
+            totals.1 += metadata.len();
+        }
+    }
+    totals
+}
+
+/// List `dirs` and ask whether to remove them; anything but yes, including no answer,
+/// keeps them
+pub fn confirm<R: BufRead, W: Write>(
+    root: &Path,
+    dirs: &[MetadataDir],
+    input: &mut R,
+    output: &mut W,
+) -> Result<bool> {
+    writeln!(
+        output,
+        "\nmacOS metadata directories at the root of {}:",
+        root.display()
+    )?;
+    for dir in dirs {
+        writeln!(
+            output,
+            "  {}  ({} files, {})",
+            dir.path.display(),
+            dir.files,
+            report::format_bytes(dir.bytes)
+        )?;
+    }
+    write!(
+        output,
+        "Remove these {} directories and everything in them? [y/N] ",
+        dirs.len()
+    )?;
+    output.flush()?;
+
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
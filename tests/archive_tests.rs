@@ -0,0 +1,299 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use dds::archives::{self, ArchiveMode};
+use dds::backend::MemoryCache;
+use dds::cache::DeletionOutcome;
+use dds::{bye_bye_ds_stores, RunOptions, RunSummary, Verbosity};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+use zip::write::SimpleFileOptions;
+
+fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut writer = zip::ZipWriter::new(fs::File::create(path).expect("Failed to create zip"));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, contents) in entries {
+        writer
+            .start_file(*name, options)
+            .expect("Failed to start entry");
+        writer.write_all(contents).expect("Failed to write entry");
+    }
+    writer.set_comment("release 1.0");
+    writer.finish().expect("Failed to finish zip");
+}
+
+fn zip_names(path: &Path) -> Vec<String> {
+    let archive = zip::ZipArchive::new(fs::File::open(path).expect("Failed to open zip"))
+        .expect("Failed to read zip");
+    archive.file_names().map(str::to_string).collect()
+}
+
+async fn run(root: &Path, dry: bool, options: RunOptions) -> RunSummary {
+    bye_bye_ds_stores(
+        root,
+        &true,
+        Verbosity::Quiet,
+        &dry,
+        &mut MemoryCache::new(168, false),
+        &options,
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Run failed")
+}
+
+#[test]
+fn macos_entries_are_recognised() {
+    assert!(archives::is_cruft("__MACOSX/"));
+    assert!(archives::is_cruft("__MACOSX/project/._readme.txt"));
+    assert!(archives::is_cruft("project/.DS_Store"));
+    assert!(archives::is_cruft(".DS_Store"));
+    assert!(!archives::is_cruft("project/readme.txt"));
+    assert!(!archives::is_cruft("project/not__MACOSX/file"));
+}
+
+#[tokio::test]
+async fn zip_archives_are_listed_and_rewritten_without_macos_entries() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let archive = tree.path().join("release.zip");
+    write_zip(
+        &archive,
+        &[
+            ("project/readme.txt", b"hello"),
+            ("project/.DS_Store", b"x"),
+            ("__MACOSX/project/._readme.txt", b"x"),
+            ("project/src/main.rs", b"fn main() {}"),
+        ],
+    );
+    write_zip(&tree.path().join("clean.zip"), &[("notes.txt", b"fine")]);
+
+    let listed = run(
+        tree.path(),
+        true,
+        RunOptions {
+            archives: ArchiveMode::Report,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(listed.archives.len(), 1);
+    assert_eq!(listed.archives[0].path, archive);
+    assert_eq!(listed.archives[0].entries, 4);
+    assert_eq!(
+        listed.archives[0].cruft,
+        ["project/.DS_Store", "__MACOSX/project/._readme.txt"]
+    );
+
+    let cleaned = run(
+        tree.path(),
+        false,
+        RunOptions {
+            archives: ArchiveMode::Clean,
+            archive_backup: true,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(cleaned.archives_cleaned, 1);
+    assert_eq!(cleaned.archives_failed, 0);
+    assert_eq!(
+        zip_names(&archive),
+        ["project/readme.txt", "project/src/main.rs"]
+    );
+    assert_eq!(zip_names(&tree.path().join("release.zip.bak")).len(), 4);
+
+    let mut rewritten = zip::ZipArchive::new(fs::File::open(&archive).expect("Failed to open zip"))
+        .expect("Failed to read zip");
+    assert_eq!(rewritten.comment(), b"release 1.0");
+    let mut contents = String::new();
+    rewritten
+        .by_name("project/readme.txt")
+        .expect("Entry missing")
+        .read_to_string(&mut contents)
+        .expect("Failed to read entry");
+    assert_eq!(contents, "hello");
+
+    // A second clean would clobber the backup, so it is refused
+    write_zip(&archive, &[("again/.DS_Store", b"x")]);
+    let refused = run(
+        tree.path(),
+        false,
+        RunOptions {
+            archives: ArchiveMode::Clean,
+            archive_backup: true,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(refused.archives_failed, 1);
+    assert_eq!(zip_names(&archive), ["again/.DS_Store"]);
+}
+
+/// (name, mode, mtime, contents) of every member, in order
+fn tar_members<R: Read>(input: R) -> Vec<(String, u32, u64, Vec<u8>)> {
+    let mut archive = tar::Archive::new(input);
+    archive
+        .entries()
+        .expect("Failed to read tar")
+        .map(|entry| {
+            let mut entry = entry.expect("Failed to read member");
+            let header = entry.header().clone();
+            let mut contents = Vec::new();
+            entry
+                .read_to_end(&mut contents)
+                .expect("Failed to read member");
+            (
+                entry
+                    .path()
+                    .expect("Bad member path")
+                    .to_string_lossy()
+                    .into_owned(),
+                header.mode().expect("Bad mode"),
+                header.mtime().expect("Bad mtime"),
+                contents,
+            )
+        })
+        .collect()
+}
+
+fn tar_bytes(long_dir: &str) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, mode, contents) in [
+        ("release/README".to_string(), 0o644, &b"read me"[..]),
+        ("release/._README".to_string(), 0o644, &b"x"[..]),
+        (format!("{long_dir}/.DS_Store"), 0o644, &b"x"[..]),
+        (format!("{long_dir}/run.sh"), 0o755, &b"#!/bin/sh"[..]),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(mode);
+        header.set_mtime(1_600_000_000);
+        builder
+            .append_data(&mut header, name, contents)
+            .expect("Failed to append member");
+    }
+    builder.into_inner().expect("Failed to finish tar")
+}
+
+#[tokio::test]
+async fn tar_archives_are_repacked_in_order_with_their_metadata() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    // Long enough to need a GNU long-name header of its own
+    let long_dir = format!("release/{}", "nested-directory-".repeat(8));
+    let tar = tar_bytes(&long_dir);
+
+    let plain = tree.path().join("release.tar");
+    fs::write(&plain, &tar).expect("Failed to write tar");
+    let gzipped = tree.path().join("release.tar.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&tar).expect("Failed to compress");
+    fs::write(&gzipped, encoder.finish().expect("Failed to compress"))
+        .expect("Failed to write tar.gz");
+    let zstd_path = tree.path().join("release.tar.zst");
+    fs::write(
+        &zstd_path,
+        zstd::encode_all(tar.as_slice(), 0).expect("Failed to compress"),
+    )
+    .expect("Failed to write tar.zst");
+
+    let listed = run(
+        tree.path(),
+        true,
+        RunOptions {
+            archives: ArchiveMode::Report,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(listed.archives.len(), 3);
+    for archive in &listed.archives {
+        assert_eq!(archive.entries, 4);
+        assert_eq!(
+            archive.cruft,
+            [
+                "release/._README".to_string(),
+                format!("{long_dir}/.DS_Store")
+            ]
+        );
+    }
+
+    let mut cache = MemoryCache::new(168, false);
+    let cleaned = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            archives: ArchiveMode::Clean,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Run failed");
+    assert_eq!(cleaned.archives_cleaned, 3);
+
+    let expected = vec![
+        (
+            "release/README".to_string(),
+            0o644,
+            1_600_000_000,
+            b"read me".to_vec(),
+        ),
+        (
+            format!("{long_dir}/run.sh"),
+            0o755,
+            1_600_000_000,
+            b"#!/bin/sh".to_vec(),
+        ),
+    ];
+    assert_eq!(
+        tar_members(fs::File::open(&plain).expect("Open failed")),
+        expected
+    );
+    assert_eq!(
+        tar_members(flate2::read::GzDecoder::new(
+            fs::File::open(&gzipped).expect("Open failed")
+        )),
+        expected
+    );
+    assert_eq!(
+        tar_members(
+            zstd::stream::read::Decoder::new(fs::File::open(&zstd_path).expect("Open failed"))
+                .expect("Failed to decompress")
+        ),
+        expected
+    );
+
+    // Every rewrite is in the audit trail, with the hash of the archive as it was
+    let audit = cache.deletions();
+    assert_eq!(audit.len(), 3);
+    assert!(audit.iter().all(
+        |record| record.outcome == DeletionOutcome::Rewritten && record.content_hash.is_some()
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_archives_are_not_rewritten() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let target = tree.path().join("real.zip");
+    write_zip(
+        &target,
+        &[("notes.txt", b"notes"), ("__MACOSX/._notes.txt", b"x")],
+    );
+    let link = tree.path().join("link.zip");
+    std::os::unix::fs::symlink(&target, &link).expect("Failed to create symlink");
+
+    let hit = archives::scan(&link)
+        .expect("Failed to scan archive")
+        .expect("The archive behind the link has cruft");
+    assert!(archives::clean(&hit, false).is_err());
+    assert!(fs::symlink_metadata(&link)
+        .expect("The link should still be there")
+        .file_type()
+        .is_symlink());
+    assert_eq!(zip_names(&target).len(), 2);
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command as Process;
+
+use clap::Parser;
+use dds::cli::{CacheCommand, Cli, Command};
+use dds::Verbosity;
+use tempfile::TempDir;
+
+#[test]
+fn several_directories_are_taken_as_roots() {
+    let cli = Cli::try_parse_from(["dds", "-r", "/srv/one", "/srv/two"]).expect("Failed to parse");
+    assert_eq!(cli.dir, ["/srv/one", "/srv/two"]);
+    assert!(cli.command.is_none());
+}
+
+#[test]
+fn subcommand_names_are_not_taken_as_roots() {
+    let cli = Cli::try_parse_from(["dds", "-r", "/some/dir", "cache", "export", "x.jsonl"])
+        .expect("Failed to parse");
+    assert_eq!(cli.dir, ["/some/dir"]);
+    match cli.command {
+        Some(Command::Cache {
+            action: CacheCommand::Export { file, .. },
+        }) => assert_eq!(file, PathBuf::from("x.jsonl")),
+        _ => panic!("Expected `cache export`"),
+    }
+}
+
+#[test]
+fn verbosity_flags_resolve_to_a_single_level() {
+    for (args, expected) in [
+        (&["dds"][..], Verbosity::Normal),
+        (&["dds", "-v"][..], Verbosity::Verbose),
+        (&["dds", "-q"][..], Verbosity::Quiet),
+        // Conflicting flags cancel out rather than erroring
+        (&["dds", "-v", "-q"][..], Verbosity::Normal),
+    ] {
+        let cli = Cli::try_parse_from(args).expect("Failed to parse");
+        assert_eq!(Verbosity::new_from_bools(cli.verbose, cli.quiet), expected);
+    }
+
+    assert!(Verbosity::Verbose.is_not_quiet());
+    assert!(!Verbosity::Quiet.is_not_quiet());
+}
+
+#[test]
+fn no_cache_leaves_the_home_directory_untouched() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let home = dir.path().join("home");
+    let tree = dir.path().join("tree");
+    fs::create_dir_all(&home).expect("Failed to create home");
+    fs::create_dir_all(tree.join("nested")).expect("Failed to create tree");
+    fs::write(tree.join("nested/.DS_Store"), b"x").expect("Failed to write file");
+
+    let output = Process::new(env!("CARGO_BIN_EXE_dds"))
+        .env("HOME", &home)
+        .args(["--no-cache", "-q", "-r"])
+        .arg(&tree)
+        .output()
+        .expect("Failed to run dds");
+    assert!(output.status.success(), "{output:?}");
+    assert!(!tree.join("nested/.DS_Store").exists());
+    assert!(!home.join(".dds").exists());
+}
+
+#[test]
+fn delete_limit_is_checked_before_the_review() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let tree = dir.path().join("tree");
+    fs::create_dir_all(tree.join("nested")).expect("Failed to create tree");
+    fs::write(tree.join(".DS_Store"), b"x").expect("Failed to write file");
+    fs::write(tree.join("nested/.DS_Store"), b"x").expect("Failed to write file");
+
+    // No answers are given, so reaching the review would end it early instead
+    let output = Process::new(env!("CARGO_BIN_EXE_dds"))
+        .env("HOME", dir.path().join("home"))
+        .args(["--no-cache", "-q", "-r", "-i", "--max-delete", "1"])
+        .arg(&tree)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to run dds");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("more than the limit of 1"), "{stderr}");
+    assert!(tree.join(".DS_Store").exists());
+}
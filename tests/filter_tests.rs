@@ -0,0 +1,87 @@
+use std::fs;
+use std::time::Duration;
+
+use dds::backend::MemoryCache;
+use dds::filters::{parse_age, parse_size, HitFilter};
+use dds::{bye_bye_ds_stores, RunOptions, Verbosity};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+#[test]
+fn ages_and_sizes_parse_with_units() {
+    assert_eq!(parse_age("30"), Ok(Duration::from_secs(30 * 86_400)));
+    assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 3600)));
+    assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 86_400)));
+    assert_eq!(parse_age("45m"), Ok(Duration::from_secs(45 * 60)));
+    assert!(parse_age("soon").is_err());
+    assert!(parse_age("3y").is_err());
+
+    assert_eq!(parse_size("6148"), Ok(6148));
+    assert_eq!(parse_size("16K"), Ok(16 * 1024));
+    assert_eq!(parse_size("5MB"), Ok(5 * 1024 * 1024));
+    assert_eq!(parse_size("1g"), Ok(1024 * 1024 * 1024));
+    assert!(parse_size("big").is_err());
+    assert!(parse_size("2T").is_err());
+}
+
+async fn run(root: &std::path::Path, filter: HitFilter) -> dds::RunSummary {
+    bye_bye_ds_stores(
+        root,
+        &true,
+        Verbosity::Quiet,
+        &true,
+        &mut MemoryCache::new(168, false),
+        &RunOptions {
+            filter,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Dry run failed")
+}
+
+#[tokio::test]
+async fn limits_apply_during_the_walk() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    fs::create_dir_all(tree.path().join("big")).expect("Failed to create dir");
+    fs::write(tree.path().join(".DS_Store"), vec![0_u8; 6148]).expect("Failed to write file");
+    fs::write(tree.path().join("big/.DS_Store"), vec![0_u8; 5 << 20])
+        .expect("Failed to write file");
+
+    let capped = run(
+        tree.path(),
+        HitFilter {
+            max_size: Some(1 << 20),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(capped.hits_found, 1);
+    assert_eq!(capped.skipped_filtered, 1);
+    assert_eq!(capped.hits[0].path, tree.path().join(".DS_Store"));
+
+    // Both files were written just now
+    let old_only = run(
+        tree.path(),
+        HitFilter {
+            older_than: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(old_only.hits_found, 0);
+    assert_eq!(old_only.skipped_filtered, 2);
+
+    let recent = run(
+        tree.path(),
+        HitFilter {
+            newer_than: Some(Duration::from_secs(3600)),
+            min_size: Some(1024),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(recent.hits_found, 2);
+    assert_eq!(recent.skipped_filtered, 0);
+}
@@ -0,0 +1,1029 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use dds::appledouble::AppleDoubleMode;
+use dds::backend::{CacheBackend, MemoryCache};
+use dds::cache::{Cache, DeletionOutcome, DeletionQuery, DirectoryStatus};
+use dds::ownership::{self, OwnerFilter};
+use dds::profiles::ProfileSet;
+use dds::roots;
+use dds::volume::{self, VolumeCleanup};
+use dds::{
+    bye_bye_ds_stores, bye_bye_ds_stores_in, html, metrics, portable, report, safety, RunOptions,
+    Verbosity,
+};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+fn make_tree(root: &Path) {
+    fs::create_dir_all(root.join("a/nested")).expect("Failed to create tree");
+    fs::create_dir_all(root.join("b")).expect("Failed to create tree");
+    fs::write(root.join(".DS_Store"), b"x").expect("Failed to write file");
+    fs::write(root.join("a/nested/.DS_Store"), b"x").expect("Failed to write file");
+    fs::write(root.join("b/keep.txt"), b"x").expect("Failed to write file");
+}
+
+#[tokio::test]
+async fn exported_scan_seeds_a_fresh_cache() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let state = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = Cache::new(&state.path().join("scan.sqlite"), 168, false)
+        .await
+        .expect("Failed to open cache");
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &true,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Dry run failed");
+
+    let mut buffer = Vec::new();
+    let records = cache.export_records().await.expect("Failed to export");
+    portable::write_records(&mut buffer, portable::PortableFormat::Jsonl, &records)
+        .expect("Failed to write records");
+
+    let mut seeded = Cache::new(&state.path().join("ci.sqlite"), 168, false)
+        .await
+        .expect("Failed to open cache");
+    let parsed = portable::read_records(buffer.as_slice(), portable::PortableFormat::Jsonl)
+        .expect("Failed to read records");
+    seeded
+        .import_records(parsed)
+        .await
+        .expect("Failed to import");
+
+    for dir in [tree.path().to_path_buf(), tree.path().join("a/nested")] {
+        assert_eq!(
+            seeded
+                .get_directory_status(&dir)
+                .await
+                .expect("Failed to get status"),
+            DirectoryStatus::Fresh,
+            "{} should be fresh after import",
+            dir.display()
+        );
+    }
+}
+
+#[tokio::test]
+async fn new_ds_store_in_cached_directory_is_picked_up() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let state = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = Cache::new(&state.path().join("cache.sqlite"), 168, false)
+        .await
+        .expect("Failed to open cache");
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    let clean_dir = tree.path().join("b");
+    assert_eq!(
+        cache
+            .get_directory_status(&clean_dir)
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+    // Directories whose .DS_Store was just deleted are re-stamped, not left stale
+    assert_eq!(
+        cache
+            .get_directory_status(&tree.path().join("a/nested"))
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    fs::write(clean_dir.join(".DS_Store"), b"x").expect("Failed to write file");
+
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert!(!clean_dir.join(".DS_Store").exists());
+}
+
+#[tokio::test]
+async fn in_memory_cache_supports_a_full_run() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = Cache::in_memory(168, false)
+        .await
+        .expect("Failed to open in-memory cache");
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    assert!(!tree.path().join(".DS_Store").exists());
+    assert!(!tree.path().join("a/nested/.DS_Store").exists());
+    assert_eq!(
+        cache
+            .get_directory_status(&tree.path().join("b"))
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+
+    // Sessions and found files round-trip within the process
+    let session_id = cache
+        .start_session(&[tree.path().to_path_buf()], true, true, "")
+        .await
+        .expect("Failed to start session");
+    let hit = tree.path().join("b/.DS_Store");
+    cache
+        .save_found_files(&session_id, std::slice::from_ref(&hit))
+        .await
+        .expect("Failed to save found files");
+    assert_eq!(
+        cache
+            .load_found_files(&session_id)
+            .await
+            .expect("Failed to load found files"),
+        vec![hit]
+    );
+}
+
+#[tokio::test]
+async fn walker_runs_against_the_memory_backend() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = MemoryCache::new(168, false);
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &true,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Dry run failed");
+
+    // Dry runs leave files alone but still record what was found
+    assert!(tree.path().join("a/nested/.DS_Store").exists());
+    assert_eq!(
+        cache
+            .get_directory_status(&tree.path().join("a/nested"))
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+    assert_eq!(
+        cache
+            .get_cached_children(tree.path())
+            .await
+            .expect("Failed to get children")
+            .len(),
+        2
+    );
+    assert!(cache.get_current_session().is_none());
+
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    assert!(!tree.path().join(".DS_Store").exists());
+    assert!(!tree.path().join("a/nested/.DS_Store").exists());
+}
+
+#[tokio::test]
+async fn memory_backend_resumes_interrupted_sessions() {
+    let mut cache = MemoryCache::new(168, false);
+    let root = Path::new("/srv/share");
+
+    let session_id = cache
+        .start_session(&[root.to_path_buf()], true, false, "")
+        .await
+        .expect("Failed to start session");
+    cache
+        .enqueue_work_batch(
+            &session_id,
+            &[root.join("x"), root.join("x"), root.join("y")],
+            0,
+        )
+        .await
+        .expect("Failed to enqueue work");
+    assert_eq!(cache.get_work_count(&session_id).await.expect("count"), 3);
+    cache
+        .interrupt_session()
+        .await
+        .expect("Failed to interrupt session");
+
+    // A different mode is a different search and must not pick up this queue
+    assert_eq!(
+        cache
+            .resume_session(&[root.to_path_buf()], true, true, "")
+            .await
+            .expect("Failed to resume session"),
+        None
+    );
+
+    // Nor does a search for other files
+    assert_eq!(
+        cache
+            .resume_session(&[root.to_path_buf()], true, false, "windows")
+            .await
+            .expect("Failed to resume session"),
+        None
+    );
+
+    let resumed = cache
+        .resume_session(&[root.to_path_buf()], true, false, "")
+        .await
+        .expect("Failed to resume session");
+    assert_eq!(resumed.as_deref(), Some(session_id.as_str()));
+}
+
+#[tokio::test]
+async fn deletions_are_recorded_in_the_audit_log() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let state = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = Cache::new(&state.path().join("audit.sqlite"), 168, false)
+        .await
+        .expect("Failed to open cache");
+    let before = chrono::Utc::now().timestamp();
+    bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    let all = cache
+        .get_deletions(&DeletionQuery::default())
+        .await
+        .expect("Failed to query deletions");
+    assert_eq!(all.len(), 2);
+    for record in &all {
+        assert_eq!(record.outcome, DeletionOutcome::Deleted);
+        assert_eq!(record.size, Some(1));
+        // SHA-256 of the single byte "x"
+        assert_eq!(
+            record.content_hash.as_deref(),
+            Some("2d711642b726b04401627ca9fbac32f5c8530fb1903cc4db02258717921a4881")
+        );
+        assert!(record.session_id.is_some());
+        assert!(record.attempted_at >= before);
+    }
+
+    let nested = cache
+        .get_deletions(&DeletionQuery {
+            prefix: Some(tree.path().join("a")),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to query deletions");
+    assert_eq!(nested.len(), 1);
+    assert_eq!(nested[0].file_path, tree.path().join("a/nested/.DS_Store"));
+
+    let future = cache
+        .get_deletions(&DeletionQuery {
+            since: Some(before + 3600),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to query deletions");
+    assert!(future.is_empty());
+}
+
+#[tokio::test]
+async fn run_summary_feeds_the_metrics_textfile() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let state = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = Cache::in_memory(168, false)
+        .await
+        .expect("Failed to open in-memory cache");
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    assert_eq!(summary.hits_found, 2);
+    assert_eq!(summary.deleted, 2);
+    assert_eq!(summary.failed, 0);
+    assert_eq!(summary.directories_searched, 4);
+
+    let stats = cache.get_cache_stats().await.expect("Failed to get stats");
+    let metrics_path = state.path().join("dds.prom");
+    metrics::write_textfile(
+        &metrics_path,
+        &metrics::render(&summary, Some(&stats), None, 1_700_000_000),
+    )
+    .expect("Failed to write metrics");
+
+    let contents = fs::read_to_string(&metrics_path).expect("Failed to read metrics");
+    let root = tree.path().display();
+    assert!(contents.contains(&format!("dds_last_run_hits_found{{root=\"{root}\"}} 2\n")));
+    assert!(contents.contains(&format!(
+        "dds_last_run_deletions{{root=\"{root}\",outcome=\"deleted\"}} 2\n"
+    )));
+    assert!(contents.contains("dds_last_run_timestamp_seconds"));
+    assert!(!contents.contains("dds_cache_size_bytes"));
+    assert!(contents.ends_with("# EOF\n"));
+    // Only the final file is left behind
+    assert_eq!(fs::read_dir(state.path()).expect("read dir").count(), 1);
+}
+
+#[tokio::test]
+async fn hit_sizes_are_recorded_and_reclaimed() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+    fs::write(tree.path().join("a/nested/.DS_Store"), vec![0_u8; 6148])
+        .expect("Failed to write file");
+
+    let mut cache = Cache::in_memory(168, false)
+        .await
+        .expect("Failed to open in-memory cache");
+    let dry = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &true,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Dry run failed");
+
+    assert_eq!(dry.bytes_found, 6149);
+    assert_eq!(dry.bytes_reclaimed, 0);
+    let mut sizes: Vec<Option<i64>> = cache
+        .get_found_history()
+        .await
+        .expect("Failed to read history")
+        .into_iter()
+        .map(|file| file.size)
+        .collect();
+    sizes.sort_unstable();
+    assert_eq!(sizes, vec![Some(1), Some(6148)]);
+
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    assert_eq!(summary.deleted, 2);
+    assert_eq!(summary.bytes_reclaimed, 6149);
+}
+
+#[tokio::test]
+async fn stored_session_renders_a_self_contained_html_report() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+    fs::create_dir_all(tree.path().join("<odd>")).expect("Failed to create dir");
+    fs::write(tree.path().join("<odd>/.DS_Store"), b"x").expect("Failed to write file");
+
+    let mut cache = Cache::in_memory(168, false)
+        .await
+        .expect("Failed to open in-memory cache");
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    let stored = html::render(
+        &report::session_report(&cache, &summary.session_id, None)
+            .await
+            .expect("Failed to build report"),
+        0,
+    );
+    assert!(stored.starts_with("<!DOCTYPE html>"));
+    assert!(stored.contains("<tr><th>.DS_Store files found</th><td class=\"num\">3</td></tr>"));
+    assert!(stored.contains("<tr><th>Deleted</th><td class=\"num\">3</td></tr>"));
+    assert!(stored.contains("&lt;odd&gt;"));
+    assert!(!stored.contains("<odd>"));
+    assert!(stored.contains("No deletions failed."));
+    assert!(!stored.contains("<script"));
+    assert!(!stored.contains("http"));
+
+    let live = html::render(
+        &report::session_report(&cache, &summary.session_id, Some(&summary))
+            .await
+            .expect("Failed to build report"),
+        0,
+    );
+    assert!(live.contains("<tr><th>Directories searched</th><td class=\"num\">5</td></tr>"));
+}
+
+#[tokio::test]
+async fn csv_report_has_one_row_per_hit() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let state = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = Cache::in_memory(168, false)
+        .await
+        .expect("Failed to open in-memory cache");
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    let session_report = report::session_report(&cache, &summary.session_id, Some(&summary))
+        .await
+        .expect("Failed to build report");
+    let csv_path = state.path().join("reports/run.csv");
+    report::write_session_report(&csv_path, report::ReportFormat::Csv, &session_report)
+        .expect("Failed to write report");
+
+    let contents = fs::read_to_string(&csv_path).expect("Failed to read report");
+    let mut lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.remove(0), "path,parent,size,mtime,owner,action,error");
+    lines.sort_unstable();
+    assert_eq!(lines.len(), 2);
+    let nested = tree.path().join("a/nested");
+    assert!(lines[1].starts_with(&format!(
+        "{}/.DS_Store,{},1,",
+        nested.display(),
+        nested.display()
+    )));
+    assert!(lines[1].ends_with(",deleted,"));
+}
+
+#[tokio::test]
+async fn rejected_files_are_left_alone_by_later_runs() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+    let kept = tree.path().join("a/nested/.DS_Store");
+
+    let mut cache = MemoryCache::new(168, false);
+    cache
+        .record_rejections("earlier-review", std::slice::from_ref(&kept))
+        .await
+        .expect("Failed to record rejection");
+
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    assert_eq!(summary.hits_found, 2);
+    assert_eq!(summary.deleted, 1);
+    assert_eq!(summary.rejected, 1);
+    assert!(kept.exists());
+    assert!(!tree.path().join(".DS_Store").exists());
+}
+
+#[tokio::test]
+async fn safety_limits_stop_deletions() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+
+    let mut cache = MemoryCache::new(168, false);
+    let over_limit = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            max_delete: Some(1),
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await;
+    assert!(over_limit.is_err());
+    assert!(tree.path().join(".DS_Store").exists());
+    assert!(cache.deletions().is_empty());
+
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            max_delete: Some(1),
+            protected_paths: safety::resolve_protected(&[tree.path().join("a")]),
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(summary.deleted, 1);
+    assert_eq!(summary.protected, 1);
+    assert!(tree.path().join("a/nested/.DS_Store").exists());
+    assert!(!tree.path().join(".DS_Store").exists());
+
+    let home = tree.path().join("a");
+    assert_eq!(
+        safety::broad_root_reason(Path::new("/"), None),
+        Some("the root of the filesystem")
+    );
+    assert_eq!(
+        safety::broad_root_reason(&home.join("nested/.."), Some(&home)),
+        Some("your home directory")
+    );
+    assert_eq!(safety::broad_root_reason(tree.path(), Some(&home)), None);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn protected_paths_hold_through_symlinked_ancestors() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let real = tree.path().join("realparent");
+    make_tree(&real.join("data"));
+    std::os::unix::fs::symlink(&real, tree.path().join("lnparent"))
+        .expect("Failed to create symlink");
+    let linked = tree.path().join("lnparent/data");
+
+    let mut cache = MemoryCache::new(168, false);
+    let summary = bye_bye_ds_stores(
+        &linked,
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            protected_paths: safety::resolve_protected(&[linked.join("a")]),
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(summary.protected, 1);
+    assert!(real.join("data/a/nested/.DS_Store").exists());
+    assert!(!real.join("data/.DS_Store").exists());
+
+    // Configured by the real path and reached through the link works as well
+    assert!(safety::is_protected(
+        &linked.join("b/.DS_Store"),
+        &safety::resolve_protected(&[real.join("data/b")])
+    ));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn files_owned_by_others_are_skipped() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    make_tree(tree.path());
+    let me = ownership::current_uid().expect("Failed to get uid");
+
+    let mut cache = MemoryCache::new(168, false);
+    let someone_else = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            owners: OwnerFilter {
+                uids: vec![me.wrapping_add(1)],
+                gids: Vec::new(),
+            },
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(someone_else.skipped_ownership, 2);
+    assert_eq!(someone_else.deleted, 0);
+    assert!(tree.path().join(".DS_Store").exists());
+
+    let mine = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            owners: OwnerFilter {
+                uids: vec![me],
+                gids: Vec::new(),
+            },
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(mine.skipped_ownership, 0);
+    assert_eq!(mine.deleted, 2);
+}
+
+#[tokio::test]
+async fn appledouble_orphans_are_removed_and_paired_files_kept() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let header = [0x00, 0x05, 0x16, 0x07, 0x00, 0x02, 0x00, 0x00];
+    fs::write(tree.path().join(".DS_Store"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("photo.jpg"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("._photo.jpg"), header).expect("Failed to write file");
+    fs::write(tree.path().join("._gone.txt"), header).expect("Failed to write file");
+    // Named like AppleDouble but without the header, so not ours to touch
+    fs::write(tree.path().join("._notes"), b"just notes").expect("Failed to write file");
+
+    let mut cache = MemoryCache::new(168, false);
+    let orphans = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            appledouble: AppleDoubleMode::Orphans,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(orphans.deleted, 2);
+    assert_eq!(orphans.skipped_paired, 1);
+    assert!(!tree.path().join("._gone.txt").exists());
+    assert!(tree.path().join("._photo.jpg").exists());
+    assert!(tree.path().join("._notes").exists());
+
+    // The directory is fresh in the cache now, but still has to be read for `._` files
+    let paired = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            appledouble: AppleDoubleMode::All,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(paired.deleted, 1);
+    assert!(!tree.path().join("._photo.jpg").exists());
+    assert!(tree.path().join("photo.jpg").exists());
+    assert!(tree.path().join("._notes").exists());
+}
+
+#[tokio::test]
+async fn volume_metadata_directories_are_listed_then_removed() {
+    let volume = TempDir::new().expect("Failed to create temp dir");
+    make_tree(volume.path());
+    fs::create_dir_all(volume.path().join(".Spotlight-V100/Store-V2"))
+        .expect("Failed to create dir");
+    fs::write(
+        volume.path().join(".Spotlight-V100/Store-V2/index"),
+        [0_u8; 100],
+    )
+    .expect("Failed to write file");
+    fs::create_dir_all(volume.path().join(".fseventsd")).expect("Failed to create dir");
+    fs::write(volume.path().join(".fseventsd/fseventsd-uuid"), b"uuid")
+        .expect("Failed to write file");
+    fs::create_dir_all(volume.path().join(".Trashes/501")).expect("Failed to create dir");
+
+    let mut cache = MemoryCache::new(168, false);
+    let listed = bye_bye_ds_stores(
+        volume.path(),
+        &true,
+        Verbosity::Quiet,
+        &true,
+        &mut cache,
+        &RunOptions {
+            volume_cleanup: VolumeCleanup::Confirmed,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Dry run failed");
+    let found: Vec<(&str, u64, u64)> = listed
+        .volume_dirs
+        .iter()
+        .map(|dir| {
+            (
+                dir.path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default(),
+                dir.files,
+                dir.bytes,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (".Spotlight-V100", 1, 100),
+            (".fseventsd", 1, 4),
+            (".Trashes", 0, 0)
+        ]
+    );
+    // File-level hits are counted separately
+    assert_eq!(listed.hits_found, 2);
+    assert!(volume.path().join(".fseventsd").exists());
+
+    let removed = bye_bye_ds_stores(
+        volume.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            volume_cleanup: VolumeCleanup::Confirmed,
+            protected_paths: vec![volume.path().join(".Trashes/501")],
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(removed.volume_dirs_removed, 2);
+    assert_eq!(removed.deleted, 2);
+    assert!(!volume.path().join(".Spotlight-V100").exists());
+    assert!(!volume.path().join(".fseventsd").exists());
+    assert!(volume.path().join(".Trashes/501").exists());
+
+    let audited: Vec<_> = cache
+        .deletions()
+        .into_iter()
+        .filter(|record| {
+            record
+                .file_path
+                .starts_with(volume.path().join(".fseventsd"))
+        })
+        .collect();
+    assert_eq!(audited.len(), 1);
+    assert_eq!(audited[0].size, Some(4));
+
+    let mut answer = std::io::Cursor::new("n\n");
+    assert!(!volume::confirm(
+        volume.path(),
+        &removed.volume_dirs,
+        &mut answer,
+        &mut Vec::new()
+    )
+    .expect("Prompt failed"));
+    let mut answer = std::io::Cursor::new("yes\n");
+    assert!(volume::confirm(
+        volume.path(),
+        &removed.volume_dirs,
+        &mut answer,
+        &mut Vec::new()
+    )
+    .expect("Prompt failed"));
+}
+
+#[tokio::test]
+async fn volume_cleanup_honours_owner_filter_and_delete_limit() {
+    let volume = TempDir::new().expect("Failed to create temp dir");
+    fs::create_dir_all(volume.path().join(".Trashes/501")).expect("Failed to create dir");
+    fs::create_dir_all(volume.path().join(".fseventsd")).expect("Failed to create dir");
+    fs::write(volume.path().join(".DS_Store"), b"x").expect("Failed to write file");
+    let me = ownership::current_uid().expect("Failed to get uid");
+
+    let mut cache = MemoryCache::new(168, false);
+    let someone_else = bye_bye_ds_stores(
+        volume.path(),
+        &false,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            volume_cleanup: VolumeCleanup::Confirmed,
+            owners: OwnerFilter {
+                uids: vec![me.wrapping_add(1)],
+                gids: Vec::new(),
+            },
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(someone_else.volume_dirs_removed, 0);
+    assert!(volume.path().join(".Trashes/501").exists());
+    assert!(volume.path().join(".fseventsd").exists());
+
+    // One file and two directories is over a limit of two
+    let over_limit = bye_bye_ds_stores(
+        volume.path(),
+        &false,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            volume_cleanup: VolumeCleanup::Confirmed,
+            max_delete: Some(2),
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await;
+    assert!(over_limit.is_err());
+    assert!(volume.path().join(".DS_Store").exists());
+    assert!(volume.path().join(".Trashes").exists());
+    assert!(volume.path().join(".fseventsd").exists());
+}
+
+#[tokio::test]
+async fn profiles_choose_which_files_are_removed() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    fs::create_dir_all(tree.path().join("share")).expect("Failed to create tree");
+    fs::write(tree.path().join(".DS_Store"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("share/Thumbs.db"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("share/desktop.ini"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("share/notes.txt.swp"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("share/notes.txt"), b"x").expect("Failed to write file");
+    let header = [0x00, 0x05, 0x16, 0x07, 0x00, 0x02, 0x00, 0x00];
+    fs::write(tree.path().join("share/x"), b"x").expect("Failed to write file");
+    fs::write(tree.path().join("share/._x"), header).expect("Failed to write file");
+    fs::write(tree.path().join("share/._plain"), b"not AppleDouble").expect("Failed to write file");
+    fs::write(tree.path().join("share/._gone"), header).expect("Failed to write file");
+
+    let custom = BTreeMap::from([("scratch".to_string(), vec!["*.swp".to_string()])]);
+    let windows =
+        ProfileSet::resolve(&["windows".to_string()], &custom).expect("Failed to resolve profiles");
+    assert_eq!(windows.key(), "windows");
+    assert_eq!(windows.files(), "files");
+    assert_eq!(ProfileSet::default().files(), ".DS_Store files");
+    assert!(ProfileSet::resolve(&["linux".to_string()], &custom).is_err());
+
+    let mut cache = MemoryCache::new(168, false);
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            profiles: windows,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(summary.deleted, 2);
+    assert!(!tree.path().join("share/Thumbs.db").exists());
+    assert!(!tree.path().join("share/desktop.ini").exists());
+    assert!(tree.path().join(".DS_Store").exists());
+
+    // Cached directories are read again for the next profile's files
+    let both = ProfileSet::resolve(&["scratch".to_string(), "mac".to_string()], &custom)
+        .expect("Failed to resolve profiles");
+    assert_eq!(both.key(), "mac,scratch");
+    let summary = bye_bye_ds_stores(
+        tree.path(),
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions {
+            profiles: both,
+            ..Default::default()
+        },
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+    assert_eq!(summary.deleted, 3);
+    assert!(!tree.path().join(".DS_Store").exists());
+    assert!(!tree.path().join("share/notes.txt.swp").exists());
+    assert!(tree.path().join("share/notes.txt").exists());
+    // `._` files picked by a profile still have to be AppleDouble orphans
+    assert!(!tree.path().join("share/._gone").exists());
+    assert!(tree.path().join("share/._x").exists());
+    assert!(tree.path().join("share/._plain").exists());
+}
+
+#[tokio::test]
+async fn several_roots_are_searched_in_one_session() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    let state = TempDir::new().expect("Failed to create temp dir");
+    for share in ["one", "two", "three"] {
+        make_tree(&tree.path().join(share));
+    }
+
+    let listed = roots::read_roots(
+        format!(
+            "# shares\n{0}/one\n\n  {0}/two  \n{0}/one/a\n",
+            tree.path().display()
+        )
+        .as_bytes(),
+    )
+    .expect("Failed to read roots");
+    assert_eq!(listed.len(), 3);
+    let given: Vec<PathBuf> = listed.iter().map(PathBuf::from).collect();
+
+    let mut cache = Cache::new(&state.path().join("roots.sqlite"), 168, false)
+        .await
+        .expect("Failed to open cache");
+    let summary = bye_bye_ds_stores_in(
+        &given,
+        &true,
+        Verbosity::Quiet,
+        &false,
+        &mut cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Cleanup failed");
+
+    // `one/a` is inside `one`, so it is not a root of its own
+    assert_eq!(
+        summary.roots,
+        vec![tree.path().join("one"), tree.path().join("two")]
+    );
+    assert_eq!(summary.root, tree.path());
+    assert_eq!(summary.deleted, 4);
+    assert!(!tree.path().join("two/a/nested/.DS_Store").exists());
+    assert!(tree.path().join("three/.DS_Store").exists());
+
+    let sessions = cache.get_sessions().await.expect("Failed to load sessions");
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].root_path, tree.path());
+    assert_eq!(sessions[0].roots, summary.roots);
+}
@@ -0,0 +1,40 @@
+use std::fs;
+
+use dds::backend::MemoryCache;
+use dds::{bye_bye_ds_stores, RunOptions, RunSummary, Verbosity};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+async fn dry_run(root: &std::path::Path, cache: &mut MemoryCache) -> RunSummary {
+    bye_bye_ds_stores(
+        root,
+        &true,
+        Verbosity::Quiet,
+        &true,
+        cache,
+        &RunOptions::default(),
+        CancellationToken::new(),
+    )
+    .await
+    .expect("Dry run failed")
+}
+
+#[tokio::test]
+async fn searched_and_skipped_directories_are_counted() {
+    let tree = TempDir::new().expect("Failed to create temp dir");
+    fs::create_dir_all(tree.path().join("a/nested")).expect("Failed to create tree");
+    fs::create_dir_all(tree.path().join("b")).expect("Failed to create tree");
+    fs::write(tree.path().join("a/.DS_Store"), b"x").expect("Failed to write file");
+
+    let mut cache = MemoryCache::new(168, false);
+    let first = dry_run(tree.path(), &mut cache).await;
+    assert_eq!(first.directories_searched, 4);
+    assert_eq!(first.directories_skipped, 0);
+    assert_eq!(first.directories_resumed, 0);
+    assert_eq!(first.hits_found, 1);
+
+    // Nothing changed, so every directory is answered from the cache
+    let second = dry_run(tree.path(), &mut cache).await;
+    assert_eq!(second.directories_searched, 0);
+    assert_eq!(second.directories_skipped, 4);
+}
@@ -0,0 +1,404 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use dds::cache::{Cache, DirectoryStamp, DirectoryState, DirectoryStatus};
+use dds::portable::{self, CacheRecord, PortableFormat};
+use tempfile::TempDir;
+
+async fn open_cache(dir: &TempDir, name: &str) -> Cache {
+    Cache::new(&dir.path().join(name), 168, false)
+        .await
+        .expect("Failed to open cache")
+}
+
+fn completed(path: &str, last_searched_at: i64, ds_store_found: bool) -> DirectoryState {
+    DirectoryState {
+        path: PathBuf::from(path),
+        last_searched_at,
+        search_completed: true,
+        ds_store_found,
+        ds_store_deleted: false,
+        error_message: None,
+        mtime_ns: None,
+        inode: None,
+    }
+}
+
+async fn seeded_records(dir: &TempDir) -> Vec<CacheRecord> {
+    let mut source = open_cache(dir, "source.sqlite").await;
+    let now = Utc::now().timestamp();
+    source
+        .mark_completed_batch(&[
+            completed("/data/a", now, true),
+            completed("/data/b", now, false),
+        ])
+        .await
+        .expect("Failed to seed directories");
+    let session_id = source
+        .start_session(&[PathBuf::from("/data")], true, true, "")
+        .await
+        .expect("Failed to start session");
+    source
+        .save_found_files(&session_id, &[PathBuf::from("/data/a/.DS_Store")])
+        .await
+        .expect("Failed to save found files");
+    source
+        .interrupt_session()
+        .await
+        .expect("Failed to interrupt session");
+
+    source.export_records().await.expect("Failed to export")
+}
+
+async fn roundtrip(format: PortableFormat) {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let records = seeded_records(&dir).await;
+
+    let mut buffer = Vec::new();
+    portable::write_records(&mut buffer, format, &records).expect("Failed to write records");
+    let parsed = portable::read_records(buffer.as_slice(), format).expect("Failed to read records");
+    assert_eq!(parsed.len(), records.len());
+
+    let mut target = open_cache(&dir, "target.sqlite").await;
+    let summary = target
+        .import_records(parsed)
+        .await
+        .expect("Failed to import");
+    assert_eq!(summary.directories, 2);
+    assert_eq!(summary.sessions, 1);
+    assert_eq!(summary.found_files, 1);
+
+    assert_eq!(
+        target
+            .get_directory_status(Path::new("/data/a"))
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+
+    let resumed = target
+        .resume_session(&[PathBuf::from("/data")], true, true, "")
+        .await
+        .expect("Failed to resume")
+        .expect("Imported interrupted session should be resumable");
+    let found = target
+        .load_found_files(&resumed)
+        .await
+        .expect("Failed to load found files");
+    assert_eq!(found, vec![PathBuf::from("/data/a/.DS_Store")]);
+}
+
+#[tokio::test]
+async fn export_import_roundtrip_jsonl() {
+    roundtrip(PortableFormat::Jsonl).await;
+}
+
+#[tokio::test]
+async fn export_import_roundtrip_csv() {
+    roundtrip(PortableFormat::Csv).await;
+}
+
+#[tokio::test]
+async fn import_keeps_newer_local_directory_entries() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let now = Utc::now().timestamp();
+
+    let mut target = open_cache(&dir, "target.sqlite").await;
+    target
+        .mark_completed_batch(&[completed("/data/a", now, false)])
+        .await
+        .expect("Failed to seed directories");
+
+    let older = vec![
+        CacheRecord::DirectoryCache(completed("/data/a", now - 60, true)),
+        CacheRecord::DirectoryCache(completed("/data/b", now - 60, true)),
+    ];
+    let summary = target
+        .import_records(older)
+        .await
+        .expect("Failed to import");
+    assert_eq!(
+        summary.directories, 1,
+        "only the unknown directory is merged"
+    );
+
+    let exported = target.export_records().await.expect("Failed to export");
+    let local_a = exported
+        .iter()
+        .find_map(|record| match record {
+            CacheRecord::DirectoryCache(state) if state.path == Path::new("/data/a") => {
+                Some(state.clone())
+            }
+            _ => None,
+        })
+        .expect("Local entry should survive the import");
+    assert_eq!(local_a.last_searched_at, now);
+    assert!(!local_a.ds_store_found);
+}
+
+#[test]
+fn format_is_inferred_from_extension() {
+    assert_eq!(
+        PortableFormat::from_path(Path::new("backup.CSV")),
+        PortableFormat::Csv
+    );
+    assert_eq!(
+        PortableFormat::from_path(Path::new("backup.jsonl")),
+        PortableFormat::Jsonl
+    );
+    assert_eq!(
+        PortableFormat::from_path(Path::new("-")),
+        PortableFormat::Jsonl
+    );
+}
+
+#[tokio::test]
+async fn changed_directory_is_stale_inside_the_window() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let watched = dir.path().join("watched");
+    std::fs::create_dir(&watched).expect("Failed to create directory");
+
+    let stamp = DirectoryStamp::of(&watched).expect("Failed to stamp directory");
+    let mut cache = open_cache(&dir, "cache.sqlite").await;
+    cache
+        .mark_completed_batch(&[DirectoryState {
+            mtime_ns: stamp.mtime_ns,
+            inode: stamp.inode,
+            ..completed(&watched.to_string_lossy(), Utc::now().timestamp(), false)
+        }])
+        .await
+        .expect("Failed to mark directory");
+    assert_eq!(
+        cache
+            .get_directory_status(&watched)
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+
+    // Give coarse filesystem clocks a chance to tick before touching the directory
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    std::fs::write(watched.join(".DS_Store"), b"x").expect("Failed to write file");
+
+    assert_eq!(
+        cache
+            .get_directory_status(&watched)
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Stale
+    );
+
+    // The stored stamp is also honoured after reopening the database
+    drop(cache);
+    let reopened = open_cache(&dir, "cache.sqlite").await;
+    assert_eq!(
+        reopened
+            .get_directory_status(&watched)
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Stale
+    );
+}
+
+#[tokio::test]
+async fn unstamped_entries_fall_back_to_age() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut cache = open_cache(&dir, "cache.sqlite").await;
+    let path = dir.path().to_string_lossy().into_owned();
+    cache
+        .mark_completed_batch(&[completed(&path, Utc::now().timestamp(), false)])
+        .await
+        .expect("Failed to mark directory");
+
+    std::fs::write(dir.path().join("new.txt"), b"x").expect("Failed to write file");
+    assert_eq!(
+        cache
+            .get_directory_status(dir.path())
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+}
+
+#[tokio::test]
+async fn forget_subtree_respects_path_boundaries() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut cache = open_cache(&dir, "cache.sqlite").await;
+    let now = Utc::now().timestamp();
+    cache
+        .mark_completed_batch(&[
+            completed("/data", now, false),
+            completed("/data/foo", now, true),
+            completed("/data/foo/inner", now, true),
+            completed("/data/foobar", now, true),
+        ])
+        .await
+        .expect("Failed to seed directories");
+    cache
+        .start_session(&[PathBuf::from("/data/foo/inner")], true, true, "")
+        .await
+        .expect("Failed to start session");
+    cache
+        .interrupt_session()
+        .await
+        .expect("Failed to interrupt session");
+
+    let summary = cache
+        .forget_subtree(Path::new("/data/foo/"))
+        .await
+        .expect("Failed to forget subtree");
+    assert_eq!(summary.directories, 2);
+    assert_eq!(summary.sessions, 1);
+
+    let status = |path: &'static str| {
+        let cache = &cache;
+        async move {
+            cache
+                .get_directory_status(Path::new(path))
+                .await
+                .expect("Failed to get status")
+        }
+    };
+    assert_eq!(status("/data/foo").await, DirectoryStatus::NotCached);
+    assert_eq!(status("/data/foo/inner").await, DirectoryStatus::NotCached);
+    assert_eq!(status("/data/foobar").await, DirectoryStatus::Fresh);
+    // The parent is expired so the next walk finds the forgotten directory again
+    assert_eq!(status("/data").await, DirectoryStatus::Stale);
+
+    let undeleted = cache
+        .get_undeleted_ds_store_files(Path::new("/data/foo"), true)
+        .await
+        .expect("Failed to list undeleted files");
+    assert!(
+        undeleted.is_empty(),
+        "/data/foobar must not match /data/foo"
+    );
+}
+
+async fn raw_update(dir: &TempDir, name: &str, sql: &str, value: i64) {
+    let url = format!("sqlite:{}", dir.path().join(name).display());
+    let pool = sqlx::SqlitePool::connect(&url)
+        .await
+        .expect("Failed to open raw database");
+    sqlx::query(sql)
+        .bind(value)
+        .execute(&pool)
+        .await
+        .expect("Failed to update sessions");
+    pool.close().await;
+}
+
+fn dead_pid() -> i64 {
+    let mut child = std::process::Command::new("true")
+        .spawn()
+        .expect("Failed to spawn child");
+    let pid = child.id();
+    child.wait().expect("Failed to wait for child");
+    i64::from(pid)
+}
+
+#[tokio::test]
+async fn live_session_is_not_resumed_by_a_second_process() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut first = open_cache(&dir, "shared.sqlite").await;
+    first
+        .start_session(&[PathBuf::from("/share")], true, false, "")
+        .await
+        .expect("Failed to start session");
+
+    let mut second = open_cache(&dir, "shared.sqlite").await;
+    let error = second
+        .resume_session(&[PathBuf::from("/share")], true, false, "")
+        .await
+        .expect_err("A live session must not be resumed");
+    assert!(error.to_string().contains("still running"));
+}
+
+#[tokio::test]
+async fn kept_alive_session_is_not_taken_over() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut first = open_cache(&dir, "shared.sqlite").await;
+    first
+        .start_session(&[PathBuf::from("/share")], true, false, "")
+        .await
+        .expect("Failed to start session");
+
+    // As if the search ended long ago and deleting has been going on since
+    raw_update(
+        &dir,
+        "shared.sqlite",
+        "UPDATE search_sessions SET heartbeat_at = ?",
+        0,
+    )
+    .await;
+    let heartbeat = first.keep_alive();
+    assert!(heartbeat.is_some());
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let mut second = open_cache(&dir, "shared.sqlite").await;
+    let error = second
+        .resume_session(&[PathBuf::from("/share")], true, false, "")
+        .await
+        .expect_err("A session kept alive must not be resumed");
+    assert!(error.to_string().contains("still running"));
+}
+
+#[tokio::test]
+async fn session_with_dead_owner_is_resumed() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut crashed = open_cache(&dir, "shared.sqlite").await;
+    let session_id = crashed
+        .start_session(&[PathBuf::from("/share")], true, false, "")
+        .await
+        .expect("Failed to start session");
+    drop(crashed);
+
+    raw_update(
+        &dir,
+        "shared.sqlite",
+        "UPDATE search_sessions SET owner_pid = ?",
+        dead_pid(),
+    )
+    .await;
+
+    let mut second = open_cache(&dir, "shared.sqlite").await;
+    let resumed = second
+        .resume_session(&[PathBuf::from("/share")], true, false, "")
+        .await
+        .expect("Failed to resume");
+    assert_eq!(resumed, Some(session_id));
+}
+
+#[tokio::test]
+async fn stale_session_cleanup_spares_live_sessions() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let mut first = open_cache(&dir, "shared.sqlite").await;
+    let live_id = first
+        .start_session(&[PathBuf::from("/share/one")], true, false, "")
+        .await
+        .expect("Failed to start session");
+
+    // Age the live session well past the cache window
+    raw_update(
+        &dir,
+        "shared.sqlite",
+        "UPDATE search_sessions SET started_at = ?",
+        0,
+    )
+    .await;
+
+    let mut second = open_cache(&dir, "shared.sqlite").await;
+    second
+        .start_session(&[PathBuf::from("/share/two")], true, false, "")
+        .await
+        .expect("Failed to start session");
+
+    assert_eq!(
+        first
+            .get_work_count(&live_id)
+            .await
+            .expect("Failed to count work"),
+        1,
+        "the live session's queue must survive another process starting up"
+    );
+}
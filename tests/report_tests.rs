@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use dds::cache::{
+    DeletionOutcome, DeletionRecord, FoundFile, Hit, SearchSession, SearchSessionStatus,
+};
+use dds::report::{
+    build_trends, format_bytes, hit_tree, render_tree, usage, TrendPeriod, UsageEntry,
+};
+
+const DAY: i64 = 86_400;
+// Monday 2024-01-01 00:00:00 UTC
+const MONDAY: i64 = 1_704_067_200;
+
+fn session(id: &str, root: &str, started_at: i64) -> SearchSession {
+    SearchSession {
+        session_id: id.to_string(),
+        root_path: PathBuf::from(root),
+        started_at,
+        completed_at: Some(started_at + 60),
+        is_recursive: true,
+        is_dry_run: false,
+        status: SearchSessionStatus::Completed,
+        profiles: String::new(),
+        roots: Vec::new(),
+    }
+}
+
+fn found(session_id: &str, path: &str, discovered_at: i64) -> FoundFile {
+    FoundFile {
+        session_id: session_id.to_string(),
+        file_path: PathBuf::from(path),
+        discovered_at,
+        size: None,
+    }
+}
+
+fn deleted(session_id: &str, path: &str, attempted_at: i64) -> DeletionRecord {
+    DeletionRecord {
+        file_path: PathBuf::from(path),
+        size: Some(6148),
+        mtime: None,
+        owner_uid: None,
+        content_hash: None,
+        session_id: Some(session_id.to_string()),
+        outcome: DeletionOutcome::Deleted,
+        error_message: None,
+        attempted_at,
+    }
+}
+
+#[test]
+fn trends_bucket_hits_by_root_and_week() {
+    let sessions = vec![
+        session("s1", "/share", MONDAY),
+        session("s2", "/share", MONDAY + 2 * DAY),
+        session("s3", "/share", MONDAY + 8 * DAY),
+    ];
+    let found = vec![
+        found("s1", "/share/a/.DS_Store", MONDAY),
+        found("s1", "/share/b/.DS_Store", MONDAY),
+        // The same file seen again in the same week is only counted once
+        found("s2", "/share/a/.DS_Store", MONDAY + 2 * DAY),
+        found("s3", "/share/a/.DS_Store", MONDAY + 8 * DAY),
+    ];
+    let deletions = vec![
+        deleted("s1", "/share/a/.DS_Store", MONDAY + 10),
+        deleted("s1", "/share/b/.DS_Store", MONDAY + 10),
+        deleted("s2", "/share/a/.DS_Store", MONDAY + 2 * DAY + 10),
+    ];
+
+    let report = build_trends(&sessions, &found, &deletions, TrendPeriod::Week, 10);
+
+    assert_eq!(report.buckets.len(), 2);
+    let first_week = &report.buckets[0];
+    assert_eq!(
+        first_week.period_start,
+        NaiveDate::from_ymd_opt(2024, 1, 1).expect("valid date")
+    );
+    assert_eq!((first_week.found, first_week.deleted), (2, 3));
+    let second_week = &report.buckets[1];
+    assert_eq!(
+        second_week.period_start,
+        NaiveDate::from_ymd_opt(2024, 1, 8).expect("valid date")
+    );
+    assert_eq!((second_week.found, second_week.deleted), (1, 0));
+
+    let daily = build_trends(&sessions, &found, &deletions, TrendPeriod::Day, 10);
+    assert_eq!(daily.buckets.len(), 3);
+}
+
+#[test]
+fn trends_measure_time_to_reinfection() {
+    let sessions = vec![
+        session("s1", "/share", MONDAY),
+        session("s2", "/share", MONDAY + DAY),
+        session("s3", "/share", MONDAY + 3 * DAY),
+    ];
+    let found = vec![
+        found("s1", "/share/a/.DS_Store", MONDAY),
+        found("s1", "/share/b/.DS_Store", MONDAY),
+        found("s2", "/share/a/.DS_Store", MONDAY + DAY),
+        found("s3", "/share/a/.DS_Store", MONDAY + 3 * DAY),
+    ];
+    let deletions = vec![
+        deleted("s1", "/share/a/.DS_Store", MONDAY),
+        deleted("s1", "/share/b/.DS_Store", MONDAY),
+        deleted("s2", "/share/a/.DS_Store", MONDAY + DAY),
+    ];
+
+    let report = build_trends(&sessions, &found, &deletions, TrendPeriod::Week, 10);
+
+    // `a` came back after one day and again after two; `b` never did
+    assert_eq!(report.reinfection_count, 2);
+    assert_eq!(report.reinfected.len(), 1);
+    assert_eq!(report.reinfected[0].path, PathBuf::from("/share/a"));
+    assert_eq!(report.reinfected[0].reinfections, 2);
+    assert_eq!(report.mean_reinfection_secs, Some(1.5 * DAY as f64));
+}
+
+#[test]
+fn usage_is_subtotalled_by_top_level_directory() {
+    let hit = |path: &str, size: Option<u64>| Hit {
+        path: PathBuf::from(path),
+        size,
+    };
+    let hits = vec![
+        hit("/nas/.DS_Store", Some(100)),
+        hit("/nas/photos/.DS_Store", Some(6148)),
+        hit("/nas/photos/2023/trip/.DS_Store", Some(12_292)),
+        hit("/nas/music/.DS_Store", Some(8196)),
+        hit("/nas/music/old/.DS_Store", None),
+    ];
+
+    let report = usage(Path::new("/nas"), &hits, 6148, 2);
+
+    assert_eq!(report.total_files, 5);
+    assert_eq!(report.total_bytes, 26_736);
+    assert_eq!(report.unsized_files, 1);
+    assert_eq!(report.reclaimed_bytes, 6148);
+    assert_eq!(
+        report.largest,
+        vec![
+            hit("/nas/photos/2023/trip/.DS_Store", Some(12_292)),
+            hit("/nas/music/.DS_Store", Some(8196)),
+        ]
+    );
+    assert_eq!(
+        report.subtotals,
+        vec![
+            UsageEntry {
+                path: PathBuf::from("/nas/photos"),
+                files: 2,
+                bytes: 18_440,
+            },
+            UsageEntry {
+                path: PathBuf::from("/nas/music"),
+                files: 2,
+                bytes: 8196,
+            },
+            UsageEntry {
+                path: PathBuf::from("/nas"),
+                files: 1,
+                bytes: 100,
+            },
+        ]
+    );
+
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(26_736), "26.1 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+}
+
+#[test]
+fn hit_tree_collapses_deep_paths_and_prunes_small_subtrees() {
+    let hits: Vec<PathBuf> = [
+        "/home/.DS_Store",
+        "/home/ann/.DS_Store",
+        "/home/ann/projects/app/.DS_Store",
+        "/home/ann/projects/app/assets/.DS_Store",
+        "/home/ann/projects/site/.DS_Store",
+        "/home/bob/.DS_Store",
+        "/home/bob/music/.DS_Store",
+        "/home/cat/.DS_Store",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+
+    let tree = hit_tree(
+        Path::new("/home"),
+        hits.iter().map(PathBuf::as_path),
+        Some(2),
+        2,
+    );
+
+    assert_eq!(tree.hits, 8);
+    assert_eq!(tree.pruned_dirs, 1);
+    assert_eq!(tree.pruned_hits, 1);
+    assert_eq!(
+        render_tree(&tree),
+        "\
+/home (8)
+├── ann (4)
+│   └── projects (3)
+├── bob (2)
+│   └── … 1 smaller directory (1)
+└── … 1 smaller directory (1)
+"
+    );
+}
@@ -0,0 +1,97 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use dds::cache::Hit;
+use dds::review::review;
+
+fn hits(paths: &[&str]) -> Vec<Hit> {
+    paths
+        .iter()
+        .map(|path| Hit {
+            path: PathBuf::from(path),
+            size: Some(6148),
+        })
+        .collect()
+}
+
+fn paths(hits: &[Hit]) -> Vec<&Path> {
+    hits.iter().map(|hit| hit.path.as_path()).collect()
+}
+
+const TREE: &[&str] = &[
+    "/nas/.DS_Store",
+    "/nas/photos/.DS_Store",
+    "/nas/photos/2023/.DS_Store",
+    "/nas/projects/app/.DS_Store",
+    "/nas/projects/app/assets/.DS_Store",
+];
+
+#[test]
+fn approve_and_reject_all_need_a_single_answer() {
+    let mut output = Vec::new();
+    let approved = review(
+        Path::new("/nas"),
+        hits(TREE),
+        &mut Cursor::new("a\n"),
+        &mut output,
+    )
+    .expect("Review failed");
+    assert_eq!(approved.approved.len(), 5);
+    assert!(approved.rejected.is_empty());
+    let shown = String::from_utf8(output).expect("utf8");
+    assert!(shown.contains("5 .DS_Store files in 5 directories under /nas"));
+
+    let rejected = review(
+        Path::new("/nas"),
+        hits(TREE),
+        &mut Cursor::new("maybe\nr\n"),
+        &mut Vec::new(),
+    )
+    .expect("Review failed");
+    assert!(rejected.approved.is_empty());
+    assert_eq!(rejected.rejected.len(), 5);
+}
+
+#[test]
+fn subtrees_and_files_can_be_decided_separately() {
+    // Root file: keep. photos subtree: delete all.
+    // projects leads straight to app, which is gone through: delete its file, keep assets.
+    let answers = "d\nn\ny\nd\ny\nn\n";
+    let mut output = Vec::new();
+    let decided = review(
+        Path::new("/nas"),
+        hits(TREE),
+        &mut Cursor::new(answers),
+        &mut output,
+    )
+    .expect("Review failed");
+
+    assert_eq!(
+        paths(&decided.approved),
+        vec![
+            Path::new("/nas/photos/.DS_Store"),
+            Path::new("/nas/photos/2023/.DS_Store"),
+            Path::new("/nas/projects/app/.DS_Store"),
+        ]
+    );
+    assert_eq!(
+        paths(&decided.rejected),
+        vec![
+            Path::new("/nas/.DS_Store"),
+            Path::new("/nas/projects/app/assets/.DS_Store"),
+        ]
+    );
+    let shown = String::from_utf8(output).expect("utf8");
+    assert!(shown.contains("/nas/projects/app (2 files)"));
+}
+
+#[test]
+fn running_out_of_answers_is_an_error() {
+    let result = review(
+        Path::new("/nas"),
+        hits(TREE),
+        &mut Cursor::new("d\ny\n"),
+        &mut Vec::new(),
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use dds::cache::{Cache, DirectoryStatus};
+use dds::migrations;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use tempfile::TempDir;
+
+async fn raw_pool(path: &Path) -> SqlitePool {
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+        .expect("Invalid database url")
+        .create_if_missing(true);
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .expect("Failed to open raw database")
+}
+
+async fn execute(pool: &SqlitePool, sql: &str) {
+    sqlx::query(sql)
+        .execute(pool)
+        .await
+        .expect("Failed to execute setup statement");
+}
+
+/// A cache database with the schema dds 0.2.0 created before versioning existed
+async fn seed_unversioned(db: &Path) {
+    let now = chrono::Utc::now().timestamp();
+    let pool = raw_pool(db).await;
+    execute(
+        &pool,
+        r"
+        CREATE TABLE directory_cache (
+            path TEXT PRIMARY KEY,
+            last_searched_at INTEGER NOT NULL,
+            search_completed BOOLEAN NOT NULL DEFAULT FALSE,
+            ds_store_found BOOLEAN NOT NULL DEFAULT FALSE,
+            ds_store_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+            error_message TEXT
+        )
+        ",
+    )
+    .await;
+    for path in ["/old", "/old/sub", "/old/sub/deeper", "/top"] {
+        sqlx::query(
+            "INSERT INTO directory_cache (path, last_searched_at, search_completed) VALUES (?, ?, TRUE)",
+        )
+        .bind(path)
+        .bind(now)
+        .execute(&pool)
+        .await
+        .expect("Failed to seed row");
+    }
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn new_database_is_created_at_latest_version() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let cache = Cache::new(&dir.path().join("cache.sqlite"), 168, false)
+        .await
+        .expect("Failed to open cache");
+
+    assert_eq!(
+        cache
+            .schema_version()
+            .await
+            .expect("Failed to read version"),
+        Some(migrations::latest_version())
+    );
+}
+
+#[tokio::test]
+async fn unversioned_database_is_upgraded_in_place() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let db = dir.path().join("cache.sqlite");
+    seed_unversioned(&db).await;
+
+    let mut cache = Cache::new(&db, 168, false)
+        .await
+        .expect("Failed to open cache");
+    assert_eq!(
+        cache
+            .schema_version()
+            .await
+            .expect("Failed to read version"),
+        Some(migrations::latest_version())
+    );
+    // Tables the old build never created are filled in by the upgrade
+    cache
+        .start_session(&[PathBuf::from("/old")], true, true, "")
+        .await
+        .expect("Session tables should exist after upgrading");
+    assert_eq!(
+        cache
+            .get_directory_status(Path::new("/old"))
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+    // Rows from before parent paths were recorded can still be descended through
+    for (parent, children) in [
+        ("/", vec!["/old", "/top"]),
+        ("/old", vec!["/old/sub"]),
+        ("/old/sub", vec!["/old/sub/deeper"]),
+    ] {
+        let mut found = cache
+            .get_cached_children(Path::new(parent))
+            .await
+            .expect("Failed to load children");
+        found.sort();
+        assert_eq!(
+            found,
+            children.iter().map(PathBuf::from).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn concurrent_upgrades_apply_each_migration_once() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let db = dir.path().join("cache.sqlite");
+    seed_unversioned(&db).await;
+
+    let (first, second) = tokio::join!(Cache::new(&db, 168, false), Cache::new(&db, 168, false));
+    for cache in [first, second] {
+        assert_eq!(
+            cache
+                .expect("Both processes should open the upgraded cache")
+                .schema_version()
+                .await
+                .expect("Failed to read version"),
+            Some(migrations::latest_version())
+        );
+    }
+}
+
+#[tokio::test]
+async fn legacy_searched_dirs_table_is_migrated() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let db = dir.path().join("cache.sqlite");
+    let now = chrono::Utc::now().timestamp();
+
+    let pool = raw_pool(&db).await;
+    execute(
+        &pool,
+        "CREATE TABLE searched_dirs (path TEXT PRIMARY KEY, last_searched_at INTEGER NOT NULL)",
+    )
+    .await;
+    sqlx::query("INSERT INTO searched_dirs VALUES ('/legacy', ?)")
+        .bind(now)
+        .execute(&pool)
+        .await
+        .expect("Failed to seed row");
+    pool.close().await;
+
+    let cache = Cache::new(&db, 168, false)
+        .await
+        .expect("Failed to open cache");
+    assert_eq!(
+        cache
+            .get_directory_status(Path::new("/legacy"))
+            .await
+            .expect("Failed to get status"),
+        DirectoryStatus::Fresh
+    );
+}
+
+#[tokio::test]
+async fn newer_database_is_refused() {
+    let dir = TempDir::new().expect("Failed to create temp dir");
+    let db = dir.path().join("cache.sqlite");
+    drop(
+        Cache::new(&db, 168, false)
+            .await
+            .expect("Failed to open cache"),
+    );
+
+    let pool = raw_pool(&db).await;
+    sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, 0)")
+        .bind(migrations::latest_version() + 1)
+        .execute(&pool)
+        .await
+        .expect("Failed to bump version");
+    pool.close().await;
+
+    let error = match Cache::new(&db, 168, false).await {
+        Ok(_) => panic!("Opening a newer database should fail"),
+        Err(error) => error.to_string(),
+    };
+    assert!(
+        error.contains("schema version"),
+        "unexpected error: {error}"
+    );
+}